@@ -2,14 +2,12 @@
 
 use embedded_hal::adc::{Channel, OneShot};
 
-use core::hint::unreachable_unchecked;
-
-use crate::{
-    gpio::{Floating, Input},
-    target::{
-        adc::config::{INPSEL_A as InputSelection, REFSEL_A as Reference, RES_A as Resolution},
-        ADC,
+use crate::target::{
+    adc::config::{
+        EXTREFSEL_A as ExternalReference, INPSEL_A as InputSelection, REFSEL_A as Reference,
+        RES_A as Resolution,
     },
+    ADC,
 };
 
 pub struct Adc(ADC);
@@ -55,6 +53,85 @@ impl Adc {
 
         Self(adc)
     }
+
+    /// Select the pin of `Reference::EXTERNAL`
+    ///
+    /// `AREF0` is P0.00, `AREF1` is P0.06. Those pins can then not be used
+    /// for anything else.
+    pub fn set_external_reference(&mut self, external_reference: ExternalReference) {
+        self.0
+            .config
+            .modify(|_, w| w.extrefsel().variant(external_reference));
+    }
+
+    /// Release the `pac::ADC` instance back
+    pub fn free(self) -> ADC {
+        self.0.enable.write(|w| w.enable().disabled());
+        self.0
+    }
+
+    /// Start a conversion of `pin` without waiting for it to finish
+    ///
+    /// The result can be collected with `poll`, or once the `END` interrupt
+    /// has fired.
+    pub fn start<PIN>(&mut self, _pin: &mut PIN)
+    where
+        PIN: Channel<Adc, ID = u8>,
+    {
+        self.0.config.modify(|_, w| match PIN::channel() {
+            0 => w.psel().analog_input0(),
+            1 => w.psel().analog_input1(),
+            2 => w.psel().analog_input2(),
+            3 => w.psel().analog_input3(),
+            4 => w.psel().analog_input4(),
+            5 => w.psel().analog_input5(),
+            6 => w.psel().analog_input6(),
+            7 => w.psel().analog_input7(),
+            // `Channel` can also be implemented outside of this crate, but
+            // no such implementation is a valid input
+            _ => unreachable!(),
+        });
+
+        self.0.events_end.write(|w| unsafe { w.bits(0) });
+        self.0.tasks_start.write(|w| unsafe { w.bits(1) });
+    }
+
+    /// Return the result of the conversion started with `start`
+    ///
+    /// Returns `WouldBlock` while the conversion is still running.
+    pub fn poll(&mut self) -> nb::Result<i16, ()> {
+        if self.0.events_end.read().bits() == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        self.0.events_end.write(|w| unsafe { w.bits(0) });
+
+        // Max resolution is 10 bits so casting is always safe
+        Ok(self.0.result.read().result().bits() as i16)
+    }
+
+    /// Is a conversion in progress?
+    pub fn is_busy(&self) -> bool {
+        self.0.busy.read().busy().is_busy()
+    }
+
+    /// Abort the conversion in progress
+    pub fn stop(&mut self) {
+        self.0.tasks_stop.write(|w| unsafe { w.bits(1) });
+    }
+
+    /// Enable the `END` interrupt
+    ///
+    /// The interrupt also has to be unmasked in the NVIC, or the handler
+    /// won't get called. `poll` clears the event.
+    pub fn enable_interrupt(&mut self) {
+        self.0.intenset.write(|w| w.end().set());
+    }
+
+    /// Disable the `END` interrupt
+    pub fn disable_interrupt(&mut self) {
+        self.0.intenclr.write(|w| w.end().clear());
+    }
 }
 
 pub struct AdcConfig {
@@ -80,54 +157,10 @@ where
 {
     type Error = ();
 
-    fn read(&mut self, _pin: &mut PIN) -> nb::Result<i16, Self::Error> {
-        match PIN::channel() {
-            0 => self.0.config.modify(|_, w| w.psel().analog_input0()),
-            1 => self.0.config.modify(|_, w| w.psel().analog_input1()),
-            2 => self.0.config.modify(|_, w| w.psel().analog_input2()),
-            3 => self.0.config.modify(|_, w| w.psel().analog_input3()),
-            4 => self.0.config.modify(|_, w| w.psel().analog_input4()),
-            5 => self.0.config.modify(|_, w| w.psel().analog_input5()),
-            6 => self.0.config.modify(|_, w| w.psel().analog_input6()),
-            7 => self.0.config.modify(|_, w| w.psel().analog_input7()),
-            // This can never happen the only analog pins have already been defined
-            // PAY CLOSE ATTENTION TO ANY CHANGES TO THIS IMPL OR THE `channel_mappings!` MACRO
-            _ => unsafe { unreachable_unchecked() },
-        }
-
-        self.0.events_end.write(|w| unsafe { w.bits(0) });
-        self.0.tasks_start.write(|w| unsafe { w.bits(1) });
-
-        while self.0.events_end.read().bits() == 0 {}
-
-        self.0.events_end.write(|w| unsafe { w.bits(0) });
-
-        // Max resolution is 10 bits so casting is always safe
-        Ok(self.0.result.read().result().bits() as i16)
+    fn read(&mut self, pin: &mut PIN) -> nb::Result<i16, Self::Error> {
+        self.start(pin);
+        nb::block!(self.poll()).map_err(nb::Error::Other)
     }
 }
 
-macro_rules! channel_mappings {
-    ($($n:expr => $pin:path),*) => {
-        $(
-            impl Channel<Adc> for $pin {
-                type ID = u8;
-
-                fn channel() -> <Self as embedded_hal::adc::Channel<Adc>>::ID {
-                    $n
-                }
-            }
-        )*
-    };
-}
-
-channel_mappings! {
-    0 => crate::gpio::p0::P0_26<Input<Floating>>,
-    1 => crate::gpio::p0::P0_27<Input<Floating>>,
-    2 => crate::gpio::p0::P0_01<Input<Floating>>,
-    3 => crate::gpio::p0::P0_02<Input<Floating>>,
-    4 => crate::gpio::p0::P0_03<Input<Floating>>,
-    5 => crate::gpio::p0::P0_04<Input<Floating>>,
-    6 => crate::gpio::p0::P0_05<Input<Floating>>,
-    7 => crate::gpio::p0::P0_06<Input<Floating>>
-}
+analog_input_channels!(Adc);
@@ -0,0 +1,253 @@
+//! HAL interface to the COMP peripheral
+//!
+//! See product specification:
+//!
+//! - nrf52810: Section 6.5
+//! - nrf52832: Section 38
+//! - nrf52840: Section 6.7
+//!
+//! The comparator compares an analog input against a reference, or against a
+//! second analog input in differential mode, and generates events when the
+//! result changes. It keeps running while the CPU sleeps, so the events can be
+//! used to wake it up.
+
+use crate::target::{
+    comp::{extrefsel::EXTREFSEL_A, psel::PSEL_A, refsel::REFSEL_A},
+    COMP,
+};
+use embedded_hal::adc::Channel;
+
+pub use crate::target::comp::mode::SP_A as SpeedMode;
+
+/// Interface to the COMP peripheral
+pub struct Comp(COMP);
+
+impl Comp {
+    /// Compare `input` against a reference
+    ///
+    /// The thresholds are set to half of the reference voltage, use
+    /// `set_thresholds` to change them or to add hysteresis.
+    pub fn single_ended<P>(comp: COMP, _input: &P, reference: Reference) -> Self
+    where
+        P: Channel<Comp, ID = u8>,
+    {
+        comp.psel
+            .write(|w| w.psel().variant(analog_input(P::channel())));
+        comp.refsel
+            .write(|w| w.refsel().variant(reference.refsel()));
+        if let Reference::Analog(n) = reference {
+            comp.extrefsel
+                .write(|w| w.extrefsel().variant(analog_reference(n)));
+        }
+        comp.mode.write(|w| w.main().se().sp().normal());
+
+        let mut comp = Comp(comp);
+        comp.set_thresholds(31, 31);
+        comp
+    }
+
+    /// Compare `positive` against `negative`
+    ///
+    /// With `hysteresis` enabled, the inputs have to differ by about 50 mV
+    /// before the result changes.
+    pub fn differential<P, N>(comp: COMP, _positive: &P, _negative: &N, hysteresis: bool) -> Self
+    where
+        P: Channel<Comp, ID = u8>,
+        N: Channel<Comp, ID = u8>,
+    {
+        comp.psel
+            .write(|w| w.psel().variant(analog_input(P::channel())));
+        comp.extrefsel
+            .write(|w| w.extrefsel().variant(analog_reference(N::channel())));
+        comp.mode.write(|w| w.main().diff().sp().normal());
+        comp.hyst.write(|w| w.hyst().bit(hysteresis));
+
+        Comp(comp)
+    }
+
+    /// Set the thresholds of single ended mode
+    ///
+    /// The input has to rise above `(up + 1) / 64 * VREF` for the result to
+    /// go up, and fall below `(down + 1) / 64 * VREF` for it to go down.
+    /// Choosing `up` above `down` adds hysteresis.
+    pub fn set_thresholds(&mut self, down: u8, up: u8) {
+        assert!(down < 64 && up < 64 && down <= up);
+
+        self.0
+            .th
+            .write(|w| unsafe { w.bits(u32::from(up) << 8 | u32::from(down)) });
+    }
+
+    /// Trade response time for current consumption
+    ///
+    /// The mode can only be changed while the comparator is stopped.
+    pub fn set_speed(&mut self, speed: SpeedMode) {
+        self.0.mode.modify(|_, w| w.sp().variant(speed));
+    }
+
+    /// Enable the comparator and block until it is ready
+    pub fn start(&mut self) {
+        self.0.enable.write(|w| w.enable().enabled());
+        self.0.events_ready.reset();
+        self.0.tasks_start.write(|w| unsafe { w.bits(1) });
+        while self.0.events_ready.read().bits() == 0 {}
+        self.0.events_ready.reset();
+    }
+
+    /// Stop and disable the comparator
+    pub fn stop(&mut self) {
+        self.0.tasks_stop.write(|w| unsafe { w.bits(1) });
+        self.0.enable.write(|w| w.enable().disabled());
+    }
+
+    /// Sample the comparator output
+    ///
+    /// The comparator has to be started first.
+    pub fn compare(&mut self) -> Output {
+        self.0.tasks_sample.write(|w| unsafe { w.bits(1) });
+        if self.0.result.read().bits() & 1 == 0 {
+            Output::Below
+        } else {
+            Output::Above
+        }
+    }
+
+    /// Enable the interrupt for an event
+    ///
+    /// The interrupt also has to be unmasked in the NVIC, or the handler
+    /// won't get called.
+    pub fn enable_interrupt(&mut self, evt: CompEvent) {
+        self.0.intenset.write(|w| unsafe { w.bits(evt.mask()) });
+    }
+
+    /// Disable the interrupt for an event
+    pub fn disable_interrupt(&mut self, evt: CompEvent) {
+        self.0.intenclr.write(|w| unsafe { w.bits(evt.mask()) });
+    }
+
+    /// Obtain the state of an event, and optionally clear the event if it is
+    /// set
+    pub fn get_event_triggered(&mut self, evt: CompEvent, clear_on_read: bool) -> bool {
+        let comp = &self.0;
+
+        match evt {
+            CompEvent::Ready => event_triggered!(comp, events_ready, clear_on_read),
+            CompEvent::Down => event_triggered!(comp, events_down, clear_on_read),
+            CompEvent::Up => event_triggered!(comp, events_up, clear_on_read),
+            CompEvent::Cross => event_triggered!(comp, events_cross, clear_on_read),
+        }
+    }
+
+    /// Stop the comparator and release the `pac::COMP` instance back
+    pub fn free(mut self) -> COMP {
+        self.stop();
+        self.0
+    }
+}
+
+/// The reference voltage of single ended mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reference {
+    /// Internal 1.2 V reference
+    Internal1V2,
+    /// Internal 1.8 V reference
+    Internal1V8,
+    /// Internal 2.4 V reference
+    Internal2V4,
+    /// The supply voltage VDD
+    Vdd,
+    /// The voltage on analog input `AIN0` to `AIN7`
+    Analog(u8),
+}
+
+impl Reference {
+    /// The analog input of `pin` as reference
+    pub fn pin<P>(_pin: &P) -> Self
+    where
+        P: Channel<Comp, ID = u8>,
+    {
+        Reference::Analog(P::channel())
+    }
+
+    /// Variant of the `REFSEL` register
+    ///
+    /// The raw values differ between the chips, so they are left to the PAC.
+    fn refsel(self) -> REFSEL_A {
+        match self {
+            Reference::Internal1V2 => REFSEL_A::INT1V2,
+            Reference::Internal1V8 => REFSEL_A::INT1V8,
+            Reference::Internal2V4 => REFSEL_A::INT2V4,
+            Reference::Vdd => REFSEL_A::VDD,
+            Reference::Analog(n) => {
+                assert!(n < 8);
+                REFSEL_A::AREF
+            }
+        }
+    }
+}
+
+/// Variant of the `PSEL` register for `AIN0` to `AIN7`
+fn analog_input(n: u8) -> PSEL_A {
+    match n {
+        0 => PSEL_A::ANALOGINPUT0,
+        1 => PSEL_A::ANALOGINPUT1,
+        2 => PSEL_A::ANALOGINPUT2,
+        3 => PSEL_A::ANALOGINPUT3,
+        4 => PSEL_A::ANALOGINPUT4,
+        5 => PSEL_A::ANALOGINPUT5,
+        6 => PSEL_A::ANALOGINPUT6,
+        7 => PSEL_A::ANALOGINPUT7,
+        _ => unreachable!(),
+    }
+}
+
+/// Variant of the `EXTREFSEL` register for `AIN0` to `AIN7`
+fn analog_reference(n: u8) -> EXTREFSEL_A {
+    match n {
+        0 => EXTREFSEL_A::ANALOGREFERENCE0,
+        1 => EXTREFSEL_A::ANALOGREFERENCE1,
+        2 => EXTREFSEL_A::ANALOGREFERENCE2,
+        3 => EXTREFSEL_A::ANALOGREFERENCE3,
+        4 => EXTREFSEL_A::ANALOGREFERENCE4,
+        5 => EXTREFSEL_A::ANALOGREFERENCE5,
+        6 => EXTREFSEL_A::ANALOGREFERENCE6,
+        7 => EXTREFSEL_A::ANALOGREFERENCE7,
+        _ => unreachable!(),
+    }
+}
+
+/// Result of a comparison
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    /// The input is below the threshold, or `positive` below `negative`
+    Below,
+    /// The input is above the threshold, or `positive` above `negative`
+    Above,
+}
+
+/// Events of the COMP peripheral
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompEvent {
+    /// The comparator has started and is ready
+    Ready,
+    /// The result went from above to below
+    Down,
+    /// The result went from below to above
+    Up,
+    /// The result changed in either direction
+    Cross,
+}
+
+impl CompEvent {
+    /// Bit of the event in `INTEN`
+    fn mask(self) -> u32 {
+        match self {
+            CompEvent::Ready => 1 << 0,
+            CompEvent::Down => 1 << 1,
+            CompEvent::Up => 1 << 2,
+            CompEvent::Cross => 1 << 3,
+        }
+    }
+}
+
+analog_input_channels!(Comp);
@@ -0,0 +1,253 @@
+//! HAL interface to the FICR core component
+//!
+//! See product specification:
+//!
+//! - nrf52810: Section 4.4
+//! - nrf52832: Section 13
+//! - nrf52840: Section 4.4
+//!
+//! The factory information configuration registers are programmed by Nordic
+//! and are read-only. None of the supported chips store ADC trim values in
+//! the FICR. The SAADC corrects its offset with the `CALIBRATEOFFSET` task
+//! instead.
+//!
+//! On the nRF9160, the FICR is only accessible from the secure domain.
+
+#[cfg(feature = "9160")]
+use crate::target::FICR_S as FICR;
+
+#[cfg(not(feature = "9160"))]
+use crate::target::FICR;
+
+/// Interface to a FICR instance
+pub struct Ficr(FICR);
+
+impl Ficr {
+    /// Construct a new `Ficr` from `pac::FICR`
+    pub fn new(ficr: FICR) -> Self {
+        Self(ficr)
+    }
+
+    /// Release the `pac::FICR` instance back
+    pub fn free(self) -> FICR {
+        self.0
+    }
+
+    /// The 64 bit unique device identifier
+    pub fn device_id(&self) -> u64 {
+        #[cfg(not(feature = "9160"))]
+        let (low, high) = (
+            self.0.deviceid[0].read().bits(),
+            self.0.deviceid[1].read().bits(),
+        );
+
+        #[cfg(feature = "9160")]
+        let (low, high) = (
+            self.0.info.deviceid[0].read().bits(),
+            self.0.info.deviceid[1].read().bits(),
+        );
+
+        u64::from(high) << 32 | u64::from(low)
+    }
+
+    /// The 48 bit device address, as used for Bluetooth Low Energy
+    #[cfg(not(feature = "9160"))]
+    pub fn device_address(&self) -> DeviceAddress {
+        let low = self.0.deviceaddr[0].read().bits().to_le_bytes();
+        let high = self.0.deviceaddr[1].read().bits().to_le_bytes();
+        let mut address = [low[0], low[1], low[2], low[3], high[0], high[1]];
+
+        let kind = if self.0.deviceaddrtype.read().bits() & 1 == 0 {
+            AddressType::Public
+        } else {
+            // A random static address must have its two most significant bits
+            // set, which the factory value doesn't guarantee
+            address[5] |= 0xC0;
+            AddressType::RandomStatic
+        };
+
+        DeviceAddress { address, kind }
+    }
+
+    /// Size of a flash page in bytes
+    pub fn code_page_size(&self) -> u32 {
+        #[cfg(not(feature = "9160"))]
+        {
+            self.0.codepagesize.read().bits()
+        }
+        #[cfg(feature = "9160")]
+        {
+            self.0.info.codepagesize.read().bits()
+        }
+    }
+
+    /// Size of the flash in bytes
+    pub fn flash_size(&self) -> u32 {
+        #[cfg(not(feature = "9160"))]
+        let pages = self.0.codesize.read().bits();
+
+        #[cfg(feature = "9160")]
+        let pages = self.0.info.codesize.read().bits();
+
+        pages * self.code_page_size()
+    }
+
+    /// Size of the RAM in bytes
+    pub fn ram_size(&self) -> u32 {
+        #[cfg(feature = "51")]
+        {
+            self.0.numramblock.read().bits() * self.0.sizeramblocks.read().bits()
+        }
+        #[cfg(not(feature = "51"))]
+        {
+            self.0.info.ram.read().bits() * 1024
+        }
+    }
+
+    /// Part, variant, package, RAM and flash information
+    #[cfg(not(feature = "51"))]
+    pub fn info(&self) -> Info {
+        Info {
+            part: self.0.info.part.read().bits(),
+            variant: self.0.info.variant.read().bits().to_be_bytes(),
+            package: Package::from_bits(self.0.info.package.read().bits()),
+            ram_kb: self.0.info.ram.read().bits(),
+            flash_kb: self.0.info.flash.read().bits(),
+        }
+    }
+
+    /// Factory calibration of the temperature sensor
+    ///
+    /// These values are loaded into the TEMP peripheral by the hardware, they
+    /// are exposed here for reference.
+    #[cfg(not(any(feature = "51", feature = "9160")))]
+    pub fn temp_calibration(&self) -> TempCalibration {
+        let temp = &self.0.temp;
+        TempCalibration {
+            a: [
+                temp.a0.read().bits(),
+                temp.a1.read().bits(),
+                temp.a2.read().bits(),
+                temp.a3.read().bits(),
+                temp.a4.read().bits(),
+                temp.a5.read().bits(),
+            ],
+            b: [
+                temp.b0.read().bits(),
+                temp.b1.read().bits(),
+                temp.b2.read().bits(),
+                temp.b3.read().bits(),
+                temp.b4.read().bits(),
+                temp.b5.read().bits(),
+            ],
+            t: [
+                temp.t0.read().bits() as u8,
+                temp.t1.read().bits() as u8,
+                temp.t2.read().bits() as u8,
+                temp.t3.read().bits() as u8,
+                temp.t4.read().bits() as u8,
+            ],
+        }
+    }
+}
+
+/// The kind of a device address
+#[cfg(not(feature = "9160"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressType {
+    /// IEEE assigned public address
+    Public,
+    /// Random static address
+    RandomStatic,
+}
+
+/// A 48 bit device address
+#[cfg(not(feature = "9160"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceAddress {
+    /// Address bytes, least significant byte first
+    pub address: [u8; 6],
+    pub kind: AddressType,
+}
+
+#[cfg(not(feature = "9160"))]
+impl DeviceAddress {
+    /// Is this a random static address?
+    pub fn is_random_static(&self) -> bool {
+        self.kind == AddressType::RandomStatic
+    }
+}
+
+/// Contents of the `INFO` registers
+#[cfg(not(feature = "51"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Info {
+    /// Part number, e.g. `0x52840`
+    pub part: u32,
+    /// Part variant in ASCII, e.g. `b"AAD0"`
+    pub variant: [u8; 4],
+    pub package: Package,
+    /// RAM size in kB
+    pub ram_kb: u32,
+    /// Flash size in kB
+    pub flash_kb: u32,
+}
+
+/// Package option of the chip
+#[cfg(not(feature = "51"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Package {
+    /// QFxx - 48-pin QFN
+    #[cfg(not(feature = "9160"))]
+    QF,
+    /// CHxx - 7x8 WLCSP 56 balls
+    #[cfg(not(feature = "9160"))]
+    CH,
+    /// CIxx - 7x8 WLCSP 56 balls
+    #[cfg(not(feature = "9160"))]
+    CI,
+    /// QIxx - 73-pin aQFN
+    #[cfg(not(feature = "9160"))]
+    QI,
+    /// CKxx - WLCSP
+    #[cfg(not(feature = "9160"))]
+    CK,
+    /// CAxx - LGA
+    #[cfg(feature = "9160")]
+    CA,
+    /// A value that is not known to this crate
+    Unknown(u32),
+}
+
+#[cfg(not(feature = "51"))]
+impl Package {
+    fn from_bits(bits: u32) -> Self {
+        match bits {
+            #[cfg(not(feature = "9160"))]
+            0x2000 => Package::QF,
+            #[cfg(not(feature = "9160"))]
+            0x2001 => Package::CH,
+            #[cfg(not(feature = "9160"))]
+            0x2002 => Package::CI,
+            #[cfg(not(feature = "9160"))]
+            0x2004 => Package::QI,
+            #[cfg(not(feature = "9160"))]
+            0x2005 => Package::CK,
+            #[cfg(feature = "9160")]
+            0x2000 => Package::CA,
+            other => Package::Unknown(other),
+        }
+    }
+}
+
+/// Factory calibration values of the temperature sensor
+///
+/// `a` and `b` hold the slope and y-intercept of the piecewise linear
+/// function, `t` the segment end points.
+#[cfg(not(any(feature = "51", feature = "9160")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TempCalibration {
+    pub a: [u32; 6],
+    pub b: [u32; 6],
+    pub t: [u8; 5],
+}
@@ -0,0 +1,985 @@
+//! A small wear-levelled key-value store on top of NOR flash
+//!
+//! The store splits the flash it is given into erase pages. One page is the
+//! active page and holds an append-only log of records. Every record consists
+//! of an 8 byte header, followed by the value padded to the flash write size:
+//!
+//! - key: `u16` (`0xFFFF` is reserved, as erased flash reads as all ones)
+//! - length: `u16` (bit 15 marks a removed key)
+//! - CRC-32 over key, length and value: `u32`
+//!
+//! The value is written before the header, so a record only becomes valid once
+//! it has been written completely. When the active page is full, the latest
+//! record of every key is copied into the next page, which then becomes the
+//! active page, and the old page is erased. The pages are used in turn, which
+//! spreads the erase cycles over the whole region.
+//!
+//! A power loss in the middle of a write leaves an incomplete record at the end
+//! of the log. It fails its CRC check and is dropped by the next compaction. A
+//! power loss in the middle of a compaction leaves a partially filled spare
+//! page, which is erased when the store is mounted again.
+//!
+//! The store is generic over `embedded_storage::nor_flash::NorFlash`, so it can
+//! be used on top of `nvmc::Nvmc`, as well as on the host with flash that is
+//! emulated in RAM.
+use core::cmp::min;
+
+use embedded_storage::nor_flash::NorFlash;
+
+/// Marks a page that is in use, "KVS1"
+const PAGE_MAGIC: u32 = 0x3153_564B;
+
+/// Size of both the page header and the record header
+const HEADER_SIZE: usize = 8;
+
+/// Key of an erased record header
+const ERASED_KEY: u16 = 0xFFFF;
+
+/// Length flag of a record that removes its key
+const REMOVED: u16 = 0x8000;
+
+/// Size of the on-stack buffer used to move data around
+const CHUNK_SIZE: usize = 32;
+
+/// Key-value store over the pages of a NOR flash
+pub struct Store<F> {
+    flash: F,
+    pages: usize,
+    active: usize,
+    sequence: u32,
+    /// Offset of the next record within the active page
+    tail: usize,
+    /// Set if the space after `tail` is not erased, e.g. after a power loss
+    dirty: bool,
+}
+
+impl<F> Store<F>
+where
+    F: NorFlash,
+{
+    /// Mount the store, formatting the flash if it doesn't contain one yet
+    ///
+    /// This also recovers from a power loss during an earlier write or
+    /// compaction. The flash must have a write size of at most 8 bytes and
+    /// contain at least two pages.
+    pub fn new(flash: F) -> Result<Self, Error<F::Error>> {
+        if HEADER_SIZE % F::WRITE_SIZE != 0
+            || F::WRITE_SIZE % F::READ_SIZE != 0
+            || F::ERASE_SIZE > REMOVED as usize
+        {
+            return Err(Error::UnsupportedFlash);
+        }
+
+        let pages = flash.capacity() / F::ERASE_SIZE;
+        if pages < 2 {
+            return Err(Error::TooFewPages);
+        }
+
+        let mut store = Store {
+            flash,
+            pages,
+            active: 0,
+            sequence: 0,
+            tail: HEADER_SIZE,
+            dirty: false,
+        };
+
+        // The page with the highest sequence number is the active one
+        let mut active = None;
+        for page in 0..pages {
+            if let Some(sequence) = store.page_sequence(page)? {
+                if active.map_or(true, |(_, s)| sequence > s) {
+                    active = Some((page, sequence));
+                }
+            }
+        }
+
+        match active {
+            Some((page, sequence)) => {
+                store.active = page;
+                store.sequence = sequence;
+            }
+            None => {
+                store.erase_page(0)?;
+                store.write_page_header(0, 0)?;
+            }
+        }
+
+        // Anything in the other pages is left over from an interrupted
+        // compaction, or is an old page that was not yet erased
+        for page in 0..pages {
+            if page != store.active && !store.page_is_erased(page)? {
+                store.erase_page(page)?;
+            }
+        }
+
+        store.scan()?;
+
+        Ok(store)
+    }
+
+    /// Release the underlying flash
+    pub fn free(self) -> F {
+        self.flash
+    }
+
+    /// The largest value that can be stored under a single key
+    pub fn max_value_len(&self) -> usize {
+        F::ERASE_SIZE - 2 * HEADER_SIZE
+    }
+
+    /// Read the value stored under `key` into `buffer`
+    ///
+    /// Returns the part of `buffer` that holds the value, or `None` if no value
+    /// is stored under `key`.
+    pub fn read<'a>(
+        &mut self,
+        key: u16,
+        buffer: &'a mut [u8],
+    ) -> Result<Option<&'a mut [u8]>, Error<F::Error>> {
+        if key == ERASED_KEY {
+            return Err(Error::InvalidKey);
+        }
+
+        let (offset, header) = match self.find(key)? {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+
+        let len = header.value_len();
+        if buffer.len() < len {
+            return Err(Error::BufferTooSmall);
+        }
+
+        let address = self.address(self.active, offset + HEADER_SIZE);
+        self.read_unaligned(address, &mut buffer[..len])?;
+
+        Ok(Some(&mut buffer[..len]))
+    }
+
+    /// Store `value` under `key`, replacing any previous value
+    pub fn write(&mut self, key: u16, value: &[u8]) -> Result<(), Error<F::Error>> {
+        if key == ERASED_KEY {
+            return Err(Error::InvalidKey);
+        }
+        if value.len() > self.max_value_len() {
+            return Err(Error::ValueTooLong);
+        }
+
+        self.append(key, value.len() as u16, value)
+    }
+
+    /// Remove the value stored under `key`
+    pub fn remove(&mut self, key: u16) -> Result<(), Error<F::Error>> {
+        if key == ERASED_KEY {
+            return Err(Error::InvalidKey);
+        }
+        if self.find(key)?.is_none() {
+            return Ok(());
+        }
+
+        self.append(key, REMOVED, &[])
+    }
+
+    /// Copy the latest value of every key into the next page and erase the
+    /// active page
+    ///
+    /// This happens automatically when the active page is full, but can be
+    /// triggered earlier, e.g. at a point in time where the latency of a page
+    /// erase doesn't matter.
+    pub fn compact(&mut self) -> Result<(), Error<F::Error>> {
+        let target = (self.active + 1) % self.pages;
+
+        // An earlier compaction that failed could have left data behind
+        if !self.page_is_erased(target)? {
+            self.erase_page(target)?;
+        }
+
+        let mut destination = HEADER_SIZE;
+        let mut offset = HEADER_SIZE;
+        while offset < self.tail {
+            let header = self.read_header(self.active, offset)?;
+            let size = record_size::<F>(&header);
+
+            if !header.is_removed() && self.is_latest(header.key, offset + size)? {
+                let from = self.address(self.active, offset);
+                let to = self.address(target, destination);
+                self.copy(from, to, size)?;
+                destination += size;
+            }
+
+            offset += size;
+        }
+
+        // Writing the page header makes the new page the active one
+        let sequence = self.sequence + 1;
+        self.write_page_header(target, sequence)?;
+
+        let old = self.active;
+        self.active = target;
+        self.sequence = sequence;
+        self.tail = destination;
+        self.dirty = false;
+
+        self.erase_page(old)
+    }
+
+    fn append(&mut self, key: u16, len: u16, value: &[u8]) -> Result<(), Error<F::Error>> {
+        let size = HEADER_SIZE + align_up(value.len(), F::WRITE_SIZE);
+
+        if self.dirty || self.tail + size > F::ERASE_SIZE {
+            self.compact()?;
+
+            if self.tail + size > F::ERASE_SIZE {
+                return Err(Error::Full);
+            }
+        }
+
+        let header = Header {
+            key,
+            len,
+            crc: crc32(crc32(0, &header_crc_bytes(key, len)), value),
+        };
+        let address = self.address(self.active, self.tail);
+
+        // Until the record is complete, the space after `tail` can't be reused
+        self.dirty = true;
+
+        // The value goes first, the header commits the record
+        self.write_unaligned(address + HEADER_SIZE, value)?;
+        self.write_bytes(address, &header.to_bytes())?;
+
+        self.dirty = false;
+        self.tail += size;
+
+        Ok(())
+    }
+
+    /// Find the end of the log in the active page
+    fn scan(&mut self) -> Result<(), Error<F::Error>> {
+        let mut offset = HEADER_SIZE;
+
+        loop {
+            if offset + HEADER_SIZE > F::ERASE_SIZE {
+                self.tail = offset;
+                self.dirty = false;
+                return Ok(());
+            }
+
+            let header = self.read_header(self.active, offset)?;
+
+            if header.is_erased() {
+                // A value could have been written without its header
+                let address = self.address(self.active, offset);
+                self.tail = offset;
+                self.dirty = !self.is_erased(address, F::ERASE_SIZE - offset)?;
+                return Ok(());
+            }
+
+            if !self.is_valid(offset, &header)? {
+                self.tail = offset;
+                self.dirty = true;
+                return Ok(());
+            }
+
+            offset += record_size::<F>(&header);
+        }
+    }
+
+    /// Find the latest record for `key` in the active page
+    fn find(&mut self, key: u16) -> Result<Option<(usize, Header)>, Error<F::Error>> {
+        let mut found = None;
+
+        let mut offset = HEADER_SIZE;
+        while offset < self.tail {
+            let header = self.read_header(self.active, offset)?;
+            if header.key == key {
+                found = Some((offset, header));
+            }
+            offset += record_size::<F>(&header);
+        }
+
+        Ok(found.filter(|(_, header)| !header.is_removed()))
+    }
+
+    /// Is there no record for `key` between `offset` and the end of the log?
+    fn is_latest(&mut self, key: u16, mut offset: usize) -> Result<bool, Error<F::Error>> {
+        while offset < self.tail {
+            let header = self.read_header(self.active, offset)?;
+            if header.key == key {
+                return Ok(false);
+            }
+            offset += record_size::<F>(&header);
+        }
+
+        Ok(true)
+    }
+
+    fn is_valid(&mut self, offset: usize, header: &Header) -> Result<bool, Error<F::Error>> {
+        if header.key == ERASED_KEY || offset + record_size::<F>(header) > F::ERASE_SIZE {
+            return Ok(false);
+        }
+
+        let mut crc = crc32(0, &header_crc_bytes(header.key, header.len));
+
+        let address = self.address(self.active, offset + HEADER_SIZE);
+        let len = header.value_len();
+        let mut buffer = [0u8; CHUNK_SIZE];
+        let mut done = 0;
+        while done < len {
+            let n = min(CHUNK_SIZE, len - done);
+            // The value is padded, so reading up to the next aligned offset is
+            // fine
+            let read_len = align_up(n, F::READ_SIZE);
+            self.read_bytes(address + done, &mut buffer[..read_len])?;
+            crc = crc32(crc, &buffer[..n]);
+            done += n;
+        }
+
+        Ok(crc == header.crc)
+    }
+
+    fn page_sequence(&mut self, page: usize) -> Result<Option<u32>, Error<F::Error>> {
+        let mut bytes = [0u8; HEADER_SIZE];
+        self.read_bytes(self.address(page, 0), &mut bytes)?;
+
+        let sequence = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        let magic = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+
+        // The sequence number is written before the magic, but don't trust it
+        // if it looks erased
+        Ok(if magic == PAGE_MAGIC && sequence != u32::max_value() {
+            Some(sequence)
+        } else {
+            None
+        })
+    }
+
+    fn write_page_header(&mut self, page: usize, sequence: u32) -> Result<(), Error<F::Error>> {
+        let mut bytes = [0u8; HEADER_SIZE];
+        bytes[..4].copy_from_slice(&sequence.to_le_bytes());
+        bytes[4..].copy_from_slice(&PAGE_MAGIC.to_le_bytes());
+
+        self.write_bytes(self.address(page, 0), &bytes)
+    }
+
+    fn read_header(&mut self, page: usize, offset: usize) -> Result<Header, Error<F::Error>> {
+        let mut bytes = [0u8; HEADER_SIZE];
+        self.read_bytes(self.address(page, offset), &mut bytes)?;
+
+        Ok(Header::from_bytes(&bytes))
+    }
+
+    fn page_is_erased(&mut self, page: usize) -> Result<bool, Error<F::Error>> {
+        self.is_erased(self.address(page, 0), F::ERASE_SIZE)
+    }
+
+    fn is_erased(&mut self, address: usize, len: usize) -> Result<bool, Error<F::Error>> {
+        let mut buffer = [0u8; CHUNK_SIZE];
+        let mut done = 0;
+        while done < len {
+            let n = min(CHUNK_SIZE, len - done);
+            self.read_bytes(address + done, &mut buffer[..n])?;
+            if buffer[..n].iter().any(|&b| b != 0xFF) {
+                return Ok(false);
+            }
+            done += n;
+        }
+
+        Ok(true)
+    }
+
+    fn erase_page(&mut self, page: usize) -> Result<(), Error<F::Error>> {
+        let from = self.address(page, 0) as u32;
+        self.flash
+            .erase(from, from + F::ERASE_SIZE as u32)
+            .map_err(Error::Flash)
+    }
+
+    /// Copy `len` bytes, which must be a multiple of the write size
+    fn copy(&mut self, from: usize, to: usize, len: usize) -> Result<(), Error<F::Error>> {
+        let mut buffer = [0u8; CHUNK_SIZE];
+        let mut done = 0;
+        while done < len {
+            let n = min(CHUNK_SIZE, len - done);
+            self.read_bytes(from + done, &mut buffer[..n])?;
+            self.write_bytes(to + done, &buffer[..n])?;
+            done += n;
+        }
+
+        Ok(())
+    }
+
+    /// Read into a buffer whose length need not be a multiple of the read size
+    fn read_unaligned(&mut self, address: usize, bytes: &mut [u8]) -> Result<(), Error<F::Error>> {
+        let aligned = bytes.len() - bytes.len() % F::READ_SIZE;
+        self.read_bytes(address, &mut bytes[..aligned])?;
+
+        if aligned < bytes.len() {
+            let mut buffer = [0u8; CHUNK_SIZE];
+            self.read_bytes(address + aligned, &mut buffer[..F::READ_SIZE])?;
+            let rest = bytes.len() - aligned;
+            bytes[aligned..].copy_from_slice(&buffer[..rest]);
+        }
+
+        Ok(())
+    }
+
+    /// Write bytes padded with `0xFF` up to the next multiple of the write size
+    fn write_unaligned(&mut self, address: usize, bytes: &[u8]) -> Result<(), Error<F::Error>> {
+        let aligned = bytes.len() - bytes.len() % F::WRITE_SIZE;
+        self.write_bytes(address, &bytes[..aligned])?;
+
+        if aligned < bytes.len() {
+            let mut buffer = [0xFFu8; HEADER_SIZE];
+            let rest = bytes.len() - aligned;
+            buffer[..rest].copy_from_slice(&bytes[aligned..]);
+            self.write_bytes(address + aligned, &buffer[..F::WRITE_SIZE])?;
+        }
+
+        Ok(())
+    }
+
+    fn read_bytes(&mut self, address: usize, bytes: &mut [u8]) -> Result<(), Error<F::Error>> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        self.flash.read(address as u32, bytes).map_err(Error::Flash)
+    }
+
+    fn write_bytes(&mut self, address: usize, bytes: &[u8]) -> Result<(), Error<F::Error>> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        self.flash
+            .write(address as u32, bytes)
+            .map_err(Error::Flash)
+    }
+
+    fn address(&self, page: usize, offset: usize) -> usize {
+        page * F::ERASE_SIZE + offset
+    }
+}
+
+#[derive(Debug)]
+pub enum Error<E> {
+    /// The underlying flash returned an error
+    Flash(E),
+    /// The flash has a write size larger than 8 bytes, or pages larger than
+    /// 32 KiB
+    UnsupportedFlash,
+    /// The flash holds less than two pages
+    TooFewPages,
+    /// `0xFFFF` can't be used as a key
+    InvalidKey,
+    /// The value doesn't fit into a single page
+    ValueTooLong,
+    /// The buffer passed to `read` is shorter than the stored value
+    BufferTooSmall,
+    /// The latest values of all keys fill the page completely
+    Full,
+}
+
+/// Header of a record in the log
+#[derive(Clone, Copy)]
+struct Header {
+    key: u16,
+    len: u16,
+    crc: u32,
+}
+
+impl Header {
+    fn from_bytes(bytes: &[u8; HEADER_SIZE]) -> Self {
+        Header {
+            key: u16::from_le_bytes([bytes[0], bytes[1]]),
+            len: u16::from_le_bytes([bytes[2], bytes[3]]),
+            crc: u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+        }
+    }
+
+    fn to_bytes(self) -> [u8; HEADER_SIZE] {
+        let mut bytes = [0u8; HEADER_SIZE];
+        bytes[..4].copy_from_slice(&header_crc_bytes(self.key, self.len));
+        bytes[4..].copy_from_slice(&self.crc.to_le_bytes());
+        bytes
+    }
+
+    fn is_erased(&self) -> bool {
+        self.key == ERASED_KEY && self.len == 0xFFFF && self.crc == 0xFFFF_FFFF
+    }
+
+    fn is_removed(&self) -> bool {
+        self.len & REMOVED != 0
+    }
+
+    fn value_len(&self) -> usize {
+        (self.len & !REMOVED) as usize
+    }
+}
+
+/// Key and length, as covered by the CRC
+fn header_crc_bytes(key: u16, len: u16) -> [u8; 4] {
+    let key = key.to_le_bytes();
+    let len = len.to_le_bytes();
+    [key[0], key[1], len[0], len[1]]
+}
+
+/// Size of a record including its header and padding
+fn record_size<F: NorFlash>(header: &Header) -> usize {
+    HEADER_SIZE + align_up(header.value_len(), F::WRITE_SIZE)
+}
+
+fn align_up(len: usize, align: usize) -> usize {
+    (len + align - 1) / align * align
+}
+
+/// CRC-32 (IEEE 802.3), can be continued by passing in a previous result
+fn crc32(crc: u32, bytes: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::{Error, Store, HEADER_SIZE};
+    use embedded_storage::nor_flash::{
+        ErrorType, NorFlash, NorFlashError, NorFlashErrorKind, ReadNorFlash,
+    };
+    use std::vec::Vec;
+
+    const PAGE_SIZE: usize = 256;
+    const PAGES: usize = 4;
+
+    /// NOR flash emulated in RAM
+    ///
+    /// Like real flash, writes can only clear bits, and every word has to be
+    /// erased before it is written. After `power_loss_after(n)`, the next `n`
+    /// word writes and page erases complete, the one after that is torn
+    /// halfway, and every access fails until `power_on` is called.
+    #[derive(Clone)]
+    struct RamFlash {
+        data: [u8; PAGE_SIZE * PAGES],
+        budget: Option<usize>,
+        powered: bool,
+        operations: usize,
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum FlashError {
+        NotAligned,
+        OutOfBounds,
+        PowerLoss,
+    }
+
+    impl NorFlashError for FlashError {
+        fn kind(&self) -> NorFlashErrorKind {
+            match self {
+                FlashError::NotAligned => NorFlashErrorKind::NotAligned,
+                FlashError::OutOfBounds => NorFlashErrorKind::OutOfBounds,
+                FlashError::PowerLoss => NorFlashErrorKind::Other,
+            }
+        }
+    }
+
+    impl RamFlash {
+        fn new() -> Self {
+            RamFlash {
+                data: [0xFF; PAGE_SIZE * PAGES],
+                budget: None,
+                powered: true,
+                operations: 0,
+            }
+        }
+
+        fn power_loss_after(&mut self, operations: usize) {
+            self.budget = Some(operations);
+        }
+
+        fn power_on(&mut self) {
+            self.budget = None;
+            self.powered = true;
+        }
+
+        fn page_is_erased(&self, page: usize) -> bool {
+            self.data[page * PAGE_SIZE..(page + 1) * PAGE_SIZE]
+                .iter()
+                .all(|&b| b == 0xFF)
+        }
+
+        /// Start a word write or page erase, returns `false` if it is torn
+        fn operation(&mut self) -> Result<bool, FlashError> {
+            if !self.powered {
+                return Err(FlashError::PowerLoss);
+            }
+            self.operations += 1;
+
+            match self.budget {
+                Some(0) => {
+                    self.powered = false;
+                    Ok(false)
+                }
+                Some(n) => {
+                    self.budget = Some(n - 1);
+                    Ok(true)
+                }
+                None => Ok(true),
+            }
+        }
+
+        fn check(&self, offset: u32, len: usize, align: usize) -> Result<(), FlashError> {
+            let offset = offset as usize;
+            if offset + len > self.data.len() {
+                return Err(FlashError::OutOfBounds);
+            }
+            if offset % align != 0 || len % align != 0 {
+                return Err(FlashError::NotAligned);
+            }
+            Ok(())
+        }
+    }
+
+    impl ErrorType for RamFlash {
+        type Error = FlashError;
+    }
+
+    impl ReadNorFlash for RamFlash {
+        const READ_SIZE: usize = 1;
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), FlashError> {
+            if !self.powered {
+                return Err(FlashError::PowerLoss);
+            }
+            self.check(offset, bytes.len(), Self::READ_SIZE)?;
+
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.data[offset..offset + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.data.len()
+        }
+    }
+
+    impl NorFlash for RamFlash {
+        const WRITE_SIZE: usize = 4;
+
+        const ERASE_SIZE: usize = PAGE_SIZE;
+
+        fn erase(&mut self, from: u32, to: u32) -> Result<(), FlashError> {
+            if from > to {
+                return Err(FlashError::OutOfBounds);
+            }
+            self.check(from, (to - from) as usize, Self::ERASE_SIZE)?;
+
+            for page in (from as usize..to as usize).step_by(PAGE_SIZE) {
+                if self.operation()? {
+                    self.data[page..page + PAGE_SIZE].fill(0xFF);
+                } else {
+                    // The page header at the start survives an erase that is
+                    // interrupted halfway
+                    self.data[page + PAGE_SIZE / 2..page + PAGE_SIZE].fill(0xFF);
+                    return Err(FlashError::PowerLoss);
+                }
+            }
+            Ok(())
+        }
+
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), FlashError> {
+            self.check(offset, bytes.len(), Self::WRITE_SIZE)?;
+
+            let offset = offset as usize;
+            for (i, word) in bytes.chunks(Self::WRITE_SIZE).enumerate() {
+                let address = offset + i * Self::WRITE_SIZE;
+                let target = &mut self.data[address..address + Self::WRITE_SIZE];
+                assert!(
+                    target.iter().all(|&b| b == 0xFF),
+                    "word at {:#x} written twice",
+                    address
+                );
+
+                let complete = self.operation()?;
+                let len = if complete { word.len() } else { word.len() / 2 };
+                let target = &mut self.data[address..address + len];
+                for (t, w) in target.iter_mut().zip(word) {
+                    *t &= w;
+                }
+                if !complete {
+                    return Err(FlashError::PowerLoss);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn mount(mut flash: RamFlash) -> Store<RamFlash> {
+        flash.power_on();
+        Store::new(flash).expect("mounting failed")
+    }
+
+    fn read(store: &mut Store<RamFlash>, key: u16) -> Option<Vec<u8>> {
+        let mut buffer = [0u8; PAGE_SIZE];
+        store
+            .read(key, &mut buffer)
+            .expect("reading failed")
+            .map(|value| value.to_vec())
+    }
+
+    fn is_power_loss<T>(result: Result<T, Error<FlashError>>) -> bool {
+        matches!(result, Err(Error::Flash(FlashError::PowerLoss)))
+    }
+
+    /// A store with two keys in page 0, and a third key that was overwritten
+    fn setup() -> Store<RamFlash> {
+        let mut store = mount(RamFlash::new());
+        store.write(1, b"one").unwrap();
+        store.write(2, b"second value").unwrap();
+        store.write(3, b"old").unwrap();
+        store.write(3, b"three").unwrap();
+        store
+    }
+
+    /// Number of flash operations that `compact` performs on `setup()`
+    fn compact_operations() -> usize {
+        let mut store = setup();
+        let before = store.flash.operations;
+        store.compact().unwrap();
+        store.flash.operations - before
+    }
+
+    fn assert_setup_intact(store: &mut Store<RamFlash>) {
+        assert_eq!(read(store, 1).as_deref(), Some(&b"one"[..]));
+        assert_eq!(read(store, 2).as_deref(), Some(&b"second value"[..]));
+        assert_eq!(read(store, 3).as_deref(), Some(&b"three"[..]));
+    }
+
+    #[test]
+    fn write_read_remove() {
+        let mut store = setup();
+        assert_setup_intact(&mut store);
+        assert_eq!(read(&mut store, 4), None);
+
+        store.remove(2).unwrap();
+        assert_eq!(read(&mut store, 2), None);
+
+        let mut store = mount(store.free());
+        assert_eq!(read(&mut store, 1).as_deref(), Some(&b"one"[..]));
+        assert_eq!(read(&mut store, 2), None);
+        assert_eq!(read(&mut store, 3).as_deref(), Some(&b"three"[..]));
+    }
+
+    #[test]
+    fn pages_are_used_in_turn() {
+        let mut store = setup();
+        for page in 1..=2 * PAGES {
+            store.compact().unwrap();
+            assert_eq!(store.active, page % PAGES);
+            assert_setup_intact(&mut store);
+        }
+
+        let mut store = mount(store.free());
+        assert_eq!(store.active, 0);
+        assert_setup_intact(&mut store);
+    }
+
+    #[test]
+    fn torn_record_at_tail() {
+        let mut store = setup();
+
+        // The 9 byte value takes 3 words, then the first word of the header
+        // is written and the second one is torn
+        store.flash.power_loss_after(4);
+        assert!(is_power_loss(store.write(3, b"new value")));
+
+        let mut store = mount(store.free());
+        assert!(store.dirty);
+        assert_setup_intact(&mut store);
+
+        // The torn record must not be written over
+        store.write(4, b"four").unwrap();
+        assert_eq!(store.active, 1);
+        assert_setup_intact(&mut store);
+        assert_eq!(read(&mut store, 4).as_deref(), Some(&b"four"[..]));
+    }
+
+    #[test]
+    fn torn_value_at_tail() {
+        let mut store = setup();
+
+        store.flash.power_loss_after(1);
+        assert!(is_power_loss(store.write(3, b"new value")));
+
+        let mut store = mount(store.free());
+        assert!(store.dirty);
+        assert_setup_intact(&mut store);
+
+        store.write(3, b"new value").unwrap();
+        assert_eq!(read(&mut store, 3).as_deref(), Some(&b"new value"[..]));
+    }
+
+    #[test]
+    fn torn_spare_page() {
+        let mut store = setup();
+
+        // Copying the first record into page 1 is interrupted
+        store.flash.power_loss_after(1);
+        assert!(is_power_loss(store.compact()));
+        assert!(!store.flash.page_is_erased(1));
+
+        let mut store = mount(store.free());
+        assert_eq!(store.active, 0);
+        assert!(store.flash.page_is_erased(1));
+        assert_setup_intact(&mut store);
+
+        store.compact().unwrap();
+        assert_eq!(store.active, 1);
+        assert_setup_intact(&mut store);
+    }
+
+    #[test]
+    fn torn_page_header() {
+        let mut store = setup();
+
+        // `compact` ends with the two words of the page header and the erase
+        // of the old page, so the second word of the header is torn
+        store.flash.power_loss_after(compact_operations() - 2);
+        assert!(is_power_loss(store.compact()));
+
+        let mut store = mount(store.free());
+        assert_eq!(store.active, 0);
+        assert!(store.flash.page_is_erased(1));
+        assert_setup_intact(&mut store);
+    }
+
+    #[test]
+    fn interrupted_erase_of_old_page() {
+        let mut store = setup();
+
+        store.flash.power_loss_after(compact_operations() - 1);
+        assert!(is_power_loss(store.compact()));
+        assert!(!store.flash.page_is_erased(0));
+
+        let mut store = mount(store.free());
+        assert_eq!(store.active, 1);
+        assert!(store.flash.page_is_erased(0));
+        assert_eq!(store.tail, {
+            // Records of 3, 12 and 5 bytes padded to the write size
+            HEADER_SIZE + (HEADER_SIZE + 4) + (HEADER_SIZE + 12) + (HEADER_SIZE + 8)
+        });
+        assert_setup_intact(&mut store);
+    }
+
+    enum Step {
+        Write(u16, &'static [u8]),
+        Remove(u16),
+        Compact,
+    }
+
+    const LONG: &[u8] = &[0x5A; 40];
+    const LONGER: &[u8] = &[0xA5; 60];
+
+    const STEPS: &[Step] = &[
+        Step::Write(1, LONG),
+        Step::Write(2, b"two"),
+        Step::Write(1, LONGER),
+        Step::Write(3, LONG),
+        Step::Remove(2),
+        Step::Write(1, LONG),
+        Step::Write(2, LONGER),
+        Step::Compact,
+        Step::Write(3, b"three"),
+        Step::Write(1, LONGER),
+        Step::Remove(3),
+        Step::Write(2, LONG),
+        Step::Write(1, b""),
+        Step::Write(3, LONGER),
+    ];
+
+    /// The value of `key` after the first `steps` steps
+    fn expected(key: u16, steps: usize) -> Option<&'static [u8]> {
+        let mut value = None;
+        for step in &STEPS[..steps] {
+            match *step {
+                Step::Write(k, v) if k == key => value = Some(v),
+                Step::Remove(k) if k == key => value = None,
+                _ => {}
+            }
+        }
+        value
+    }
+
+    fn matches_steps(store: &mut Store<RamFlash>, steps: usize) -> bool {
+        (1..=3).all(|key| read(store, key).as_deref() == expected(key, steps))
+    }
+
+    #[test]
+    fn power_loss_at_every_operation() {
+        for budget in 0.. {
+            assert!(budget < 10_000, "the steps never completed");
+
+            let mut flash = RamFlash::new();
+            flash.power_loss_after(budget);
+
+            // Formatting the empty flash can be interrupted as well. Borrow
+            // the flash, so that the interrupted image can be mounted again.
+            match Store::new(&mut flash) {
+                Ok(_) => {}
+                Err(Error::Flash(FlashError::PowerLoss)) => {
+                    let mut store = mount(flash);
+                    assert!(matches_steps(&mut store, 0));
+                    continue;
+                }
+                Err(_) => panic!("mounting failed"),
+            }
+            let mut store = Store::new(flash).expect("mounting failed");
+
+            let mut done = 0;
+            let mut lost = false;
+            for step in STEPS {
+                let result = match *step {
+                    Step::Write(key, value) => store.write(key, value),
+                    Step::Remove(key) => store.remove(key),
+                    Step::Compact => store.compact(),
+                };
+                match result {
+                    Ok(()) => done += 1,
+                    Err(Error::Flash(FlashError::PowerLoss)) => {
+                        lost = true;
+                        break;
+                    }
+                    Err(_) => panic!("step {} failed", done),
+                }
+            }
+
+            if !lost {
+                assert!(matches_steps(&mut store, STEPS.len()));
+                return;
+            }
+
+            // The interrupted step either happened completely or not at all
+            let mut store = mount(store.free());
+            assert!(
+                matches_steps(&mut store, done) || matches_steps(&mut store, done + 1),
+                "wrong values after a power loss in step {} at operation {}",
+                done,
+                budget
+            );
+
+            // The store has to be usable again
+            store.write(4, LONG).unwrap();
+            assert_eq!(read(&mut store, 4).as_deref(), Some(LONG));
+        }
+    }
+}
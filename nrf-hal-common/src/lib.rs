@@ -17,12 +17,26 @@ pub use nrf52840_pac as target;
 #[cfg(feature = "9160")]
 pub use nrf9160_pac as target;
 
+#[macro_use]
+mod macros;
+
 #[cfg(feature = "51")]
 pub mod adc;
 pub mod clocks;
+#[cfg(not(any(feature = "51", feature = "9160")))]
+pub mod comp;
 #[cfg(not(feature = "51"))]
 pub mod delay;
+pub mod ficr;
 pub mod gpio;
+pub mod kvstore;
+#[cfg(all(not(feature = "51"), any(feature = "log", feature = "defmt-logger")))]
+pub mod logger;
+#[cfg(not(any(feature = "52810", feature = "9160")))]
+pub mod lpcomp;
+pub mod nvmc;
+#[cfg(not(any(feature = "51", feature = "9160")))]
+pub mod ram;
 #[cfg(not(feature = "9160"))]
 pub mod rng;
 pub mod rtc;
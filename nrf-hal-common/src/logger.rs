@@ -0,0 +1,343 @@
+//! Logging over a `Uarte`
+//!
+//! With the `log` feature, this is a backend for the `log` crate facade.
+//! With the `defmt-logger` feature, it is the `defmt` global logger, and the
+//! `defmt` frames are sent instead of text. Only one of the two should be
+//! used at a time, as both end up on the same `Uarte`.
+//!
+//! Log messages are written into a buffer of `LOG_BUFFER_SIZE` bytes, which
+//! can be done from any context, including interrupt handlers. They are sent
+//! by `LogFlusher::flush`, which never blocks and can be called from the idle
+//! loop, a periodic task or the UARTE interrupt. A message that doesn't fit
+//! into the buffer is dropped as a whole, so that the buffer never holds part
+//! of a message or `defmt` frame. `dropped` counts the dropped messages.
+//!
+//! Every message starts with the counter value of an `Rtc`, in ticks.
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use cortex_m::interrupt;
+
+use crate::rtc::{self, Rtc, Started};
+use crate::uarte::{self, Transfer, Uarte};
+
+/// Size of the buffer that holds the messages that have not been sent yet
+pub const LOG_BUFFER_SIZE: usize = 1024;
+
+/// Size of the chunks that are sent by a single EasyDMA transfer
+const CHUNK_SIZE: usize = 64;
+
+/// Messages that have not been sent yet
+///
+/// Only accessed within critical sections.
+static QUEUE: Shared<Queue> = Shared::new(Queue {
+    buffer: [0; LOG_BUFFER_SIZE],
+    start: 0,
+    len: 0,
+    message: 0,
+    dropping: false,
+});
+
+/// Bytes that are currently being sent
+///
+/// Only accessed by the `LogFlusher`, while no transfer is in progress.
+static CHUNK: Shared<[u8; CHUNK_SIZE]> = Shared::new([0; CHUNK_SIZE]);
+
+/// Address of the counter register of the `Rtc` used for timestamps
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of messages dropped because the buffer was full
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+/// Has `init` been called?
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// Start logging over `uarte`, with timestamps from `rtc`
+///
+/// With the `log` feature, this installs the logger and enables all levels.
+/// Use `log::set_max_level` to filter them. The `rtc` has to keep running,
+/// or all messages will have the same timestamp.
+///
+/// # Panics
+///
+/// If called more than once, or if another `log` backend has already been
+/// installed.
+pub fn init<T, R>(uarte: Uarte<T>, rtc: &Rtc<R, Started>) -> LogFlusher<T>
+where
+    T: uarte::Instance,
+    R: rtc::Instance,
+{
+    assert!(!INITIALIZED.swap(true, Ordering::SeqCst));
+
+    COUNTER.store(rtc.counter_ptr() as usize, Ordering::SeqCst);
+
+    #[cfg(feature = "log")]
+    {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+    }
+
+    LogFlusher {
+        state: Some(State::Idle(uarte)),
+    }
+}
+
+/// Counter value of the timestamp `Rtc`, or `0` before `init`
+pub fn timestamp() -> u32 {
+    match COUNTER.load(Ordering::Relaxed) {
+        0 => 0,
+        address => unsafe { core::ptr::read_volatile(address as *const u32) },
+    }
+}
+
+/// Number of messages that have been dropped because the buffer was full
+pub fn dropped() -> usize {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+/// Start a message, which is then written by `push` and `end_message`
+///
+/// The whole message has to be written within one critical section.
+fn start_message() {
+    let queue = unsafe { &mut *QUEUE.get() };
+    queue.message = 0;
+    queue.dropping = false;
+}
+
+/// Append `bytes` to the current message, or drop the whole message if they
+/// don't fit into the buffer
+///
+/// Has to be called within a critical section.
+fn push(bytes: &[u8]) {
+    let queue = unsafe { &mut *QUEUE.get() };
+    if queue.dropping {
+        return;
+    }
+
+    if bytes.len() > LOG_BUFFER_SIZE - queue.len {
+        // Nothing is sent while the message is written, so its beginning is
+        // still at the end of the buffer
+        queue.len -= queue.message;
+        queue.dropping = true;
+        return;
+    }
+
+    for &byte in bytes {
+        let end = (queue.start + queue.len) % LOG_BUFFER_SIZE;
+        queue.buffer[end] = byte;
+        queue.len += 1;
+    }
+    queue.message += bytes.len();
+}
+
+/// End the current message, and count it if it was dropped
+///
+/// Has to be called within the critical section that the message was
+/// written in.
+fn end_message() {
+    let queue = unsafe { &mut *QUEUE.get() };
+    if queue.dropping {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// A `static` whose accesses are synchronized by the code using it
+struct Shared<T>(UnsafeCell<T>);
+
+unsafe impl<T> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    const fn new(value: T) -> Self {
+        Shared(UnsafeCell::new(value))
+    }
+
+    fn get(&self) -> *mut T {
+        self.0.get()
+    }
+}
+
+/// Ring buffer of message bytes
+struct Queue {
+    buffer: [u8; LOG_BUFFER_SIZE],
+    start: usize,
+    len: usize,
+    /// Number of bytes of the current message in the buffer
+    message: usize,
+    /// Is the current message being dropped?
+    dropping: bool,
+}
+
+/// Sends the buffered messages over the `Uarte`
+pub struct LogFlusher<T> {
+    // Only `None` while switching between the states
+    state: Option<State<T>>,
+}
+
+enum State<T> {
+    Idle(Uarte<T>),
+    Sending(Transfer<T, &'static [u8]>),
+}
+
+impl<T> LogFlusher<T>
+where
+    T: uarte::Instance,
+{
+    /// Send the next part of the buffered messages, if the previous one has
+    /// been sent
+    ///
+    /// This never blocks. Returns `true` when there is nothing left to send.
+    pub fn flush(&mut self) -> bool {
+        let uarte = match self.state.take().unwrap() {
+            State::Sending(transfer) if !transfer.is_done() => {
+                self.state = Some(State::Sending(transfer));
+                return false;
+            }
+            State::Sending(transfer) => transfer.wait().0,
+            State::Idle(uarte) => uarte,
+        };
+
+        // Move the next chunk out of the buffer. No transfer is in progress,
+        // so `CHUNK` is not read by EasyDMA.
+        let n = interrupt::free(|_| {
+            let (queue, chunk) = unsafe { (&mut *QUEUE.get(), &mut *CHUNK.get()) };
+            let n = queue.len.min(CHUNK_SIZE);
+            for byte in chunk[..n].iter_mut() {
+                *byte = queue.buffer[queue.start];
+                queue.start = (queue.start + 1) % LOG_BUFFER_SIZE;
+            }
+            queue.len -= n;
+            n
+        });
+
+        if n == 0 {
+            self.state = Some(State::Idle(uarte));
+            return true;
+        }
+
+        let chunk: &'static [u8] = unsafe { &(*CHUNK.get())[..n] };
+        self.state = Some(match uarte.start_write(chunk) {
+            Ok(transfer) => State::Sending(transfer),
+            // `CHUNK` is in RAM and not too long, so this can't happen.
+            // Drop the chunk rather than panicking in the logger.
+            Err((_, uarte)) => State::Idle(uarte),
+        });
+        false
+    }
+
+    /// Wait for the current transfer to end, and return the `Uarte`
+    ///
+    /// Messages that are still buffered are not sent.
+    pub fn free(self) -> Uarte<T> {
+        match self.state.unwrap() {
+            State::Idle(uarte) => uarte,
+            State::Sending(transfer) => transfer.wait().0,
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+static LOGGER: Logger = Logger;
+
+/// The `log` backend
+#[cfg(feature = "log")]
+struct Logger;
+
+#[cfg(feature = "log")]
+impl log::Log for Logger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        use core::fmt::Write;
+
+        // Format the whole message within one critical section, so that
+        // messages from interrupt handlers are not mixed into it
+        interrupt::free(|_| {
+            start_message();
+            let _ = write!(
+                QueueWriter,
+                "{:>10} {:<5} {}: {}\r\n",
+                timestamp(),
+                record.level(),
+                record.target(),
+                record.args()
+            );
+            end_message();
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+/// Writes formatted text into the buffer
+///
+/// Has to be used within a critical section.
+#[cfg(feature = "log")]
+struct QueueWriter;
+
+#[cfg(feature = "log")]
+impl core::fmt::Write for QueueWriter {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        push(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "defmt-logger")]
+defmt::timestamp!("{=u32}", timestamp());
+
+/// Is a `defmt` frame being written?
+#[cfg(feature = "defmt-logger")]
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// Were interrupts enabled before the frame was started?
+#[cfg(feature = "defmt-logger")]
+static RESTORE: AtomicBool = AtomicBool::new(false);
+
+/// Only accessed between `acquire` and `release`
+#[cfg(feature = "defmt-logger")]
+static ENCODER: Shared<defmt::Encoder> = Shared::new(defmt::Encoder::new());
+
+/// The `defmt` global logger
+#[cfg(feature = "defmt-logger")]
+#[defmt::global_logger]
+struct DefmtLogger;
+
+#[cfg(feature = "defmt-logger")]
+unsafe impl defmt::Logger for DefmtLogger {
+    fn acquire() {
+        // Write each frame within one critical section, so that frames from
+        // interrupt handlers are not mixed into it
+        let active = cortex_m::register::primask::read().is_active();
+        interrupt::disable();
+
+        if TAKEN.swap(true, Ordering::Relaxed) {
+            panic!("defmt logger taken reentrantly");
+        }
+
+        RESTORE.store(active, Ordering::Relaxed);
+        start_message();
+        unsafe { (*ENCODER.get()).start_frame(push) };
+    }
+
+    unsafe fn flush() {
+        // Sending is done by `LogFlusher::flush`, and can't be waited for
+        // here without blocking
+    }
+
+    unsafe fn release() {
+        (*ENCODER.get()).end_frame(push);
+        end_message();
+        TAKEN.store(false, Ordering::Relaxed);
+
+        if RESTORE.load(Ordering::Relaxed) {
+            interrupt::enable();
+        }
+    }
+
+    unsafe fn write(bytes: &[u8]) {
+        (*ENCODER.get()).write(bytes, push);
+    }
+}
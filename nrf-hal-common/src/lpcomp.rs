@@ -0,0 +1,253 @@
+//! HAL interface to the LPCOMP peripheral
+//!
+//! See product specification:
+//!
+//! - nrf51: Section 34
+//! - nrf52832: Section 35
+//! - nrf52840: Section 6.13
+//!
+//! The low power comparator compares an analog input against a fraction of
+//! VDD or an external reference. Unlike the COMP, it keeps running in System
+//! OFF and can wake the chip from it.
+
+use crate::target::{
+    lpcomp::{extrefsel::EXTREFSEL_A, psel::PSEL_A, refsel::REFSEL_A},
+    LPCOMP,
+};
+use embedded_hal::adc::Channel;
+
+/// Interface to the LPCOMP peripheral
+pub struct Lpcomp(LPCOMP);
+
+impl Lpcomp {
+    /// Compare `input` against `reference`
+    pub fn new<P>(lpcomp: LPCOMP, _input: &P, reference: Reference) -> Self
+    where
+        P: Channel<Lpcomp, ID = u8>,
+    {
+        lpcomp
+            .psel
+            .write(|w| w.psel().variant(analog_input(P::channel())));
+        lpcomp
+            .refsel
+            .write(|w| w.refsel().variant(reference.refsel()));
+        match reference {
+            Reference::Aref0 => lpcomp
+                .extrefsel
+                .write(|w| w.extrefsel().variant(EXTREFSEL_A::ANALOGREFERENCE0)),
+            Reference::Aref1 => lpcomp
+                .extrefsel
+                .write(|w| w.extrefsel().variant(EXTREFSEL_A::ANALOGREFERENCE1)),
+            _ => {}
+        }
+
+        Lpcomp(lpcomp)
+    }
+
+    /// Select which change of the result is reported as `ANADETECT`, the
+    /// signal that wakes the chip from System OFF
+    pub fn set_detection(&mut self, detect: Detect) {
+        self.0.anadetect.write(|w| match detect {
+            Detect::Cross => w.anadetect().cross(),
+            Detect::Up => w.anadetect().up(),
+            Detect::Down => w.anadetect().down(),
+        });
+    }
+
+    /// Enable or disable the 50 mV hysteresis
+    #[cfg(not(feature = "51"))]
+    pub fn set_hysteresis(&mut self, enabled: bool) {
+        self.0.hyst.write(|w| w.hyst().bit(enabled));
+    }
+
+    /// Enable the comparator and block until it is ready
+    pub fn start(&mut self) {
+        self.0.enable.write(|w| w.enable().enabled());
+        self.0.events_ready.reset();
+        self.0.tasks_start.write(|w| unsafe { w.bits(1) });
+        while self.0.events_ready.read().bits() == 0 {}
+        self.0.events_ready.reset();
+    }
+
+    /// Stop and disable the comparator
+    pub fn stop(&mut self) {
+        self.0.tasks_stop.write(|w| unsafe { w.bits(1) });
+        self.0.enable.write(|w| w.enable().disabled());
+    }
+
+    /// Arm the comparator as a wake-up source from System OFF
+    ///
+    /// This starts the comparator with the given detection type. The chip
+    /// wakes up with a reset when the detection triggers after it has entered
+    /// System OFF. Entering System OFF while the detection condition is
+    /// already met wakes it up immediately, so check `compare` first.
+    pub fn arm_wakeup(&mut self, detect: Detect) {
+        self.set_detection(detect);
+        self.start();
+    }
+
+    /// Sample the comparator output
+    ///
+    /// The comparator has to be started first.
+    pub fn compare(&mut self) -> Output {
+        self.0.tasks_sample.write(|w| unsafe { w.bits(1) });
+        if self.0.result.read().bits() & 1 == 0 {
+            Output::Below
+        } else {
+            Output::Above
+        }
+    }
+
+    /// Enable the interrupt for an event
+    ///
+    /// The interrupt also has to be unmasked in the NVIC, or the handler
+    /// won't get called.
+    pub fn enable_interrupt(&mut self, evt: LpcompEvent) {
+        self.0.intenset.write(|w| unsafe { w.bits(evt.mask()) });
+    }
+
+    /// Disable the interrupt for an event
+    pub fn disable_interrupt(&mut self, evt: LpcompEvent) {
+        self.0.intenclr.write(|w| unsafe { w.bits(evt.mask()) });
+    }
+
+    /// Obtain the state of an event, and optionally clear the event if it is
+    /// set
+    pub fn get_event_triggered(&mut self, evt: LpcompEvent, clear_on_read: bool) -> bool {
+        let lpcomp = &self.0;
+
+        match evt {
+            LpcompEvent::Ready => event_triggered!(lpcomp, events_ready, clear_on_read),
+            LpcompEvent::Down => event_triggered!(lpcomp, events_down, clear_on_read),
+            LpcompEvent::Up => event_triggered!(lpcomp, events_up, clear_on_read),
+            LpcompEvent::Cross => event_triggered!(lpcomp, events_cross, clear_on_read),
+        }
+    }
+
+    /// Stop the comparator and release the `pac::LPCOMP` instance back
+    pub fn free(mut self) -> LPCOMP {
+        self.stop();
+        self.0
+    }
+}
+
+/// The reference voltage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reference {
+    /// `n / 8` of VDD, for `n` from 1 to 7
+    VddEighths(u8),
+    /// `n / 16` of VDD, for odd `n` from 1 to 15
+    #[cfg(not(feature = "51"))]
+    VddSixteenths(u8),
+    /// External reference on `AREF0`, which is P0.00 on the nRF51 and `AIN0`
+    /// (P0.02) on the other chips
+    Aref0,
+    /// External reference on `AREF1`, which is P0.06 on the nRF51 and `AIN1`
+    /// (P0.03) on the other chips
+    Aref1,
+}
+
+impl Reference {
+    /// Variant of the `REFSEL` register
+    fn refsel(self) -> REFSEL_A {
+        match self {
+            #[cfg(feature = "51")]
+            Reference::VddEighths(n) => match n {
+                1 => REFSEL_A::SUPPLYONEEIGHTHPRESCALING,
+                2 => REFSEL_A::SUPPLYTWOEIGHTHSPRESCALING,
+                3 => REFSEL_A::SUPPLYTHREEEIGHTHSPRESCALING,
+                4 => REFSEL_A::SUPPLYFOUREIGHTHSPRESCALING,
+                5 => REFSEL_A::SUPPLYFIVEEIGHTHSPRESCALING,
+                6 => REFSEL_A::SUPPLYSIXEIGHTHSPRESCALING,
+                7 => REFSEL_A::SUPPLYSEVENEIGHTHSPRESCALING,
+                _ => panic!("invalid fraction of VDD"),
+            },
+            #[cfg(not(feature = "51"))]
+            Reference::VddEighths(n) => match n {
+                1 => REFSEL_A::REF1_8,
+                2 => REFSEL_A::REF2_8,
+                3 => REFSEL_A::REF3_8,
+                4 => REFSEL_A::REF4_8,
+                5 => REFSEL_A::REF5_8,
+                6 => REFSEL_A::REF6_8,
+                7 => REFSEL_A::REF7_8,
+                _ => panic!("invalid fraction of VDD"),
+            },
+            #[cfg(not(feature = "51"))]
+            Reference::VddSixteenths(n) => match n {
+                1 => REFSEL_A::REF1_16,
+                3 => REFSEL_A::REF3_16,
+                5 => REFSEL_A::REF5_16,
+                7 => REFSEL_A::REF7_16,
+                9 => REFSEL_A::REF9_16,
+                11 => REFSEL_A::REF11_16,
+                13 => REFSEL_A::REF13_16,
+                15 => REFSEL_A::REF15_16,
+                _ => panic!("invalid fraction of VDD"),
+            },
+            Reference::Aref0 | Reference::Aref1 => REFSEL_A::AREF,
+        }
+    }
+}
+
+/// Variant of the `PSEL` register for `AIN0` to `AIN7`
+fn analog_input(n: u8) -> PSEL_A {
+    match n {
+        0 => PSEL_A::ANALOGINPUT0,
+        1 => PSEL_A::ANALOGINPUT1,
+        2 => PSEL_A::ANALOGINPUT2,
+        3 => PSEL_A::ANALOGINPUT3,
+        4 => PSEL_A::ANALOGINPUT4,
+        5 => PSEL_A::ANALOGINPUT5,
+        6 => PSEL_A::ANALOGINPUT6,
+        7 => PSEL_A::ANALOGINPUT7,
+        _ => unreachable!(),
+    }
+}
+
+/// Change of the result that is reported as `ANADETECT`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Detect {
+    /// The input crosses the reference in either direction
+    Cross,
+    /// The input rises above the reference
+    Up,
+    /// The input falls below the reference
+    Down,
+}
+
+/// Result of a comparison
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    /// The input is below the reference
+    Below,
+    /// The input is above the reference
+    Above,
+}
+
+/// Events of the LPCOMP peripheral
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LpcompEvent {
+    /// The comparator has started and is ready
+    Ready,
+    /// The result went from above to below
+    Down,
+    /// The result went from below to above
+    Up,
+    /// The result changed in either direction
+    Cross,
+}
+
+impl LpcompEvent {
+    /// Bit of the event in `INTEN`
+    fn mask(self) -> u32 {
+        match self {
+            LpcompEvent::Ready => 1 << 0,
+            LpcompEvent::Down => 1 << 1,
+            LpcompEvent::Up => 1 << 2,
+            LpcompEvent::Cross => 1 << 3,
+        }
+    }
+}
+
+analog_input_channels!(Lpcomp);
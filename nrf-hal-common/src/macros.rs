@@ -0,0 +1,74 @@
+//! Macros shared by the peripheral drivers
+
+/// Obtain the state of the event register `$event` of `$periph`, and clear the
+/// event if it is set and `$clear_on_read` is `true`
+///
+/// Each event has a distinct register type, so this can't be a function.
+macro_rules! event_triggered {
+    ($periph:expr, $event:ident, $clear_on_read:expr) => {{
+        let triggered = $periph.$event.read().bits() != 0;
+        if triggered && $clear_on_read {
+            $periph.$event.reset();
+        }
+        triggered
+    }};
+}
+
+/// Implement `Channel<$adc>` for the pins of the analog inputs `AIN0` to
+/// `AIN7`
+///
+/// The analog inputs work with a pin in any mode, but an enabled output or
+/// pull resistor will of course affect the result.
+macro_rules! analog_input_channels {
+    ($adc:ty) => {
+        #[cfg(feature = "51")]
+        analog_input_channels! {
+            $adc:
+            0 => P0_26,
+            1 => P0_27,
+            2 => P0_01,
+            3 => P0_02,
+            4 => P0_03,
+            5 => P0_04,
+            6 => P0_05,
+            7 => P0_06
+        }
+
+        #[cfg(feature = "9160")]
+        analog_input_channels! {
+            $adc:
+            0 => P0_13,
+            1 => P0_14,
+            2 => P0_15,
+            3 => P0_16,
+            4 => P0_17,
+            5 => P0_18,
+            6 => P0_19,
+            7 => P0_20
+        }
+
+        #[cfg(not(any(feature = "51", feature = "9160")))]
+        analog_input_channels! {
+            $adc:
+            0 => P0_02,
+            1 => P0_03,
+            2 => P0_04,
+            3 => P0_05,
+            4 => P0_28,
+            5 => P0_29,
+            6 => P0_30,
+            7 => P0_31
+        }
+    };
+    ($adc:ty: $($n:expr => $PXi:ident),*) => {
+        $(
+            impl<MODE> embedded_hal::adc::Channel<$adc> for crate::gpio::p0::$PXi<MODE> {
+                type ID = u8;
+
+                fn channel() -> <Self as embedded_hal::adc::Channel<$adc>>::ID {
+                    $n
+                }
+            }
+        )*
+    };
+}
@@ -0,0 +1,500 @@
+//! HAL interface to the Non-Volatile Memory Controller (NVMC) peripheral
+//!
+//! See product specification:
+//!
+//! - nrf51: Section 6
+//! - nrf52810: Section 4.3
+//! - nrf52832: Section 11
+//! - nrf52840: Section 4.3
+//! - nrf9160: Section 4.3
+//!
+//! `Nvmc` exposes a region of the internal flash through the
+//! `embedded-storage` NOR flash traits. All register accesses go through the
+//! `Instance` trait, so the driver can also be run against a model of the
+//! NVMC that is backed by RAM.
+use core::ptr;
+
+use embedded_storage::nor_flash::{
+    ErrorType, NorFlash, NorFlashError, NorFlashErrorKind, ReadNorFlash,
+};
+
+#[cfg(feature = "9160")]
+use crate::target::{NVMC_NS, NVMC_S};
+
+#[cfg(not(feature = "9160"))]
+use crate::target::NVMC;
+
+/// Size of a flash page in bytes, the smallest unit that can be erased
+#[cfg(feature = "51")]
+pub const PAGE_SIZE: usize = 1024;
+
+/// Size of a flash page in bytes, the smallest unit that can be erased
+#[cfg(not(feature = "51"))]
+pub const PAGE_SIZE: usize = 4096;
+
+/// Size of a flash word in bytes, the smallest unit that can be written
+pub const WORD_SIZE: usize = 4;
+
+/// Interface to a region of the internal flash
+///
+/// The region is given as a `&'static mut [u32]` covering whole flash pages,
+/// which is usually created from linker symbols that reserve space for it.
+/// Offsets passed to the `embedded-storage` traits are relative to the start
+/// of this region.
+pub struct Nvmc<T: Instance> {
+    nvmc: T,
+    storage: &'static mut [u32],
+}
+
+impl<T> Nvmc<T>
+where
+    T: Instance,
+{
+    /// Construct a new `Nvmc` from an NVMC instance and the flash region it
+    /// is allowed to modify
+    ///
+    /// The region must start on a page boundary and span whole pages.
+    pub fn new(nvmc: T, storage: &'static mut [u32]) -> Self {
+        assert!(storage.as_ptr() as usize % PAGE_SIZE == 0);
+        assert!((storage.len() * WORD_SIZE) % PAGE_SIZE == 0);
+
+        Self { nvmc, storage }
+    }
+
+    /// Release the NVMC instance and the flash region back
+    pub fn free(self) -> (T, &'static mut [u32]) {
+        (self.nvmc, self.storage)
+    }
+
+    /// Erase the page starting at `offset` bytes into the region
+    fn erase_page(&mut self, offset: usize) {
+        let address = self.word_address(offset);
+
+        self.nvmc.enable_erase();
+        // The page lies within our region and `CONFIG.WEN` allows erasing
+        unsafe { self.nvmc.erase_page(address) };
+        self.nvmc.wait_ready();
+        self.nvmc.enable_read();
+    }
+
+    /// Write `bytes` word by word, starting at `offset` bytes into the region
+    fn write_words(&mut self, offset: usize, bytes: &[u8]) {
+        self.nvmc.enable_write();
+        for (i, chunk) in bytes.chunks(WORD_SIZE).enumerate() {
+            let word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+            let address = self.word_address(offset + i * WORD_SIZE);
+
+            self.nvmc.wait_ready();
+            // The word lies within our region and `CONFIG.WEN` allows writing
+            unsafe { self.nvmc.write_word(address, word) };
+        }
+        self.nvmc.wait_ready();
+        self.nvmc.enable_read();
+    }
+
+    /// Pointer to the word at `offset` bytes into the region
+    ///
+    /// It is derived from the whole region, so that an erase through it may
+    /// cover the rest of the page.
+    fn word_address(&mut self, offset: usize) -> *mut u32 {
+        assert!(offset < self.storage.len() * WORD_SIZE);
+        // The offset lies within the region
+        unsafe { self.storage.as_mut_ptr().add(offset / WORD_SIZE) }
+    }
+
+    fn check_bounds(&self, offset: u32, len: usize) -> Result<(), Error> {
+        let offset = offset as usize;
+        let capacity = self.storage.len() * WORD_SIZE;
+        if len > capacity || offset > capacity - len {
+            return Err(Error::OutOfBounds);
+        }
+        Ok(())
+    }
+}
+
+impl<T> ErrorType for Nvmc<T>
+where
+    T: Instance,
+{
+    type Error = Error;
+}
+
+impl<T> ReadNorFlash for Nvmc<T>
+where
+    T: Instance,
+{
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Error> {
+        self.check_bounds(offset, bytes.len())?;
+
+        let base = self.storage.as_ptr() as *const u8;
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            // Flash can be modified by the NVMC behind our back, so don't let
+            // the compiler cache any reads
+            *byte = unsafe { ptr::read_volatile(base.add(offset as usize + i)) };
+        }
+
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.storage.len() * WORD_SIZE
+    }
+}
+
+impl<T> NorFlash for Nvmc<T>
+where
+    T: Instance,
+{
+    const WRITE_SIZE: usize = WORD_SIZE;
+
+    const ERASE_SIZE: usize = PAGE_SIZE;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Error> {
+        if from > to {
+            return Err(Error::OutOfBounds);
+        }
+        self.check_bounds(from, (to - from) as usize)?;
+        if from as usize % PAGE_SIZE != 0 || to as usize % PAGE_SIZE != 0 {
+            return Err(Error::NotAligned);
+        }
+
+        for page in (from as usize..to as usize).step_by(PAGE_SIZE) {
+            self.erase_page(page);
+        }
+
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Error> {
+        self.check_bounds(offset, bytes.len())?;
+        if offset as usize % WORD_SIZE != 0 || bytes.len() % WORD_SIZE != 0 {
+            return Err(Error::NotAligned);
+        }
+
+        self.write_words(offset as usize, bytes);
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// The offset or length is not a multiple of the write or erase size
+    NotAligned,
+    /// The access does not lie within the flash region
+    OutOfBounds,
+}
+
+impl NorFlashError for Error {
+    fn kind(&self) -> NorFlashErrorKind {
+        match self {
+            Error::NotAligned => NorFlashErrorKind::NotAligned,
+            Error::OutOfBounds => NorFlashErrorKind::OutOfBounds,
+        }
+    }
+}
+
+/// Implemented by all NVMC instances
+///
+/// Besides the NVMC peripheral itself, this can be implemented by a model of
+/// the NVMC that operates on RAM, e.g. to test code that uses `Nvmc` on the
+/// host.
+pub trait Instance {
+    /// Set `CONFIG.WEN` to read-only
+    fn enable_read(&self);
+
+    /// Set `CONFIG.WEN` to allow writing
+    fn enable_write(&self);
+
+    /// Set `CONFIG.WEN` to allow erasing
+    fn enable_erase(&self);
+
+    /// Block until `READY` signals that the last operation has completed
+    fn wait_ready(&self);
+
+    /// Start erasing the page that begins at `address`
+    ///
+    /// # Safety
+    ///
+    /// `address` must point to the start of a flash page, and erasing must
+    /// have been enabled with `enable_erase`.
+    unsafe fn erase_page(&self, address: *mut u32);
+
+    /// Start writing `word` to `address`
+    ///
+    /// Writing can only change bits from `1` to `0`.
+    ///
+    /// # Safety
+    ///
+    /// `address` must point to a word aligned location in flash, and writing
+    /// must have been enabled with `enable_write`.
+    unsafe fn write_word(&self, address: *mut u32, word: u32);
+}
+
+macro_rules! impl_instance {
+    ($($name:ident: $config:ident,)*) => {
+        $(
+            impl Instance for $name {
+                fn enable_read(&self) {
+                    self.$config.write(|w| w.wen().ren());
+                }
+
+                fn enable_write(&self) {
+                    self.$config.write(|w| w.wen().wen());
+                }
+
+                fn enable_erase(&self) {
+                    self.$config.write(|w| w.wen().een());
+                }
+
+                fn wait_ready(&self) {
+                    while self.ready.read().ready().is_busy() {}
+                }
+
+                unsafe fn erase_page(&self, address: *mut u32) {
+                    #[cfg(not(feature = "9160"))]
+                    self.erasepage().write(|w| w.bits(address as u32));
+
+                    // The nRF9160 erases a page when any word of it is written
+                    // while `WEN` is set to erase
+                    #[cfg(feature = "9160")]
+                    ptr::write_volatile(address, 0xFFFF_FFFF);
+                }
+
+                unsafe fn write_word(&self, address: *mut u32, word: u32) {
+                    ptr::write_volatile(address, word);
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(not(feature = "9160"))]
+impl_instance!(NVMC: config,);
+
+// On the nRF9160, `CONFIG` is only accessible from the secure domain, the
+// non-secure alias has its own `CONFIGNS` register
+#[cfg(feature = "9160")]
+impl_instance!(NVMC_NS: configns, NVMC_S: config,);
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::{Error, Instance, Nvmc, PAGE_SIZE, WORD_SIZE};
+    use core::cell::{Cell, RefCell};
+    use core::ptr;
+    use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+    use std::boxed::Box;
+    use std::vec::Vec;
+
+    const WORDS: usize = 2 * PAGE_SIZE / WORD_SIZE;
+
+    #[repr(align(4096))]
+    struct Region([u32; WORDS]);
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Wen {
+        Read,
+        Write,
+        Erase,
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Operation {
+        Erase(usize),
+        Write(usize, u32),
+    }
+
+    /// Model of the NVMC that operates on RAM
+    ///
+    /// It checks that `CONFIG.WEN` allows every operation, and that the NVMC
+    /// is ready whenever an operation is started or `CONFIG.WEN` is changed.
+    struct RamNvmc {
+        base: usize,
+        wen: Cell<Wen>,
+        busy: Cell<bool>,
+        log: RefCell<Vec<Operation>>,
+    }
+
+    impl RamNvmc {
+        fn set_wen(&self, wen: Wen) {
+            assert!(!self.busy.get(), "CONFIG changed while busy");
+            self.wen.set(wen);
+        }
+
+        fn start(&self, wen: Wen, address: *mut u32) -> usize {
+            assert_eq!(self.wen.get(), wen);
+            assert!(!self.busy.get(), "operation started while busy");
+            self.busy.set(true);
+            address as usize - self.base
+        }
+    }
+
+    impl Instance for RamNvmc {
+        fn enable_read(&self) {
+            self.set_wen(Wen::Read);
+        }
+
+        fn enable_write(&self) {
+            self.set_wen(Wen::Write);
+        }
+
+        fn enable_erase(&self) {
+            self.set_wen(Wen::Erase);
+        }
+
+        fn wait_ready(&self) {
+            self.busy.set(false);
+        }
+
+        unsafe fn erase_page(&self, address: *mut u32) {
+            let offset = self.start(Wen::Erase, address);
+            assert_eq!(offset % PAGE_SIZE, 0);
+
+            for i in 0..PAGE_SIZE / WORD_SIZE {
+                ptr::write_volatile(address.add(i), 0xFFFF_FFFF);
+            }
+            self.log.borrow_mut().push(Operation::Erase(offset));
+        }
+
+        unsafe fn write_word(&self, address: *mut u32, word: u32) {
+            let offset = self.start(Wen::Write, address);
+            assert_eq!(offset % WORD_SIZE, 0);
+
+            ptr::write_volatile(address, ptr::read_volatile(address) & word);
+            self.log.borrow_mut().push(Operation::Write(offset, word));
+        }
+    }
+
+    /// Two pages of flash, which initially hold zeros
+    fn nvmc() -> Nvmc<RamNvmc> {
+        let region = Box::leak(Box::new(Region([0; WORDS])));
+        let instance = RamNvmc {
+            base: region.0.as_ptr() as usize,
+            wen: Cell::new(Wen::Read),
+            busy: Cell::new(false),
+            log: RefCell::new(Vec::new()),
+        };
+
+        Nvmc::new(instance, &mut region.0)
+    }
+
+    /// Return the logged operations, and check that the NVMC is back to
+    /// read-only
+    fn finish(nvmc: &mut Nvmc<RamNvmc>) -> Vec<Operation> {
+        assert_eq!(nvmc.nvmc.wen.get(), Wen::Read);
+        assert!(!nvmc.nvmc.busy.get());
+        nvmc.nvmc.log.replace(Vec::new())
+    }
+
+    fn read_all(nvmc: &mut Nvmc<RamNvmc>) -> Vec<u8> {
+        let mut bytes = std::vec![0; 2 * PAGE_SIZE];
+        nvmc.read(0, &mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn erase() {
+        let mut nvmc = nvmc();
+        assert_eq!(nvmc.capacity(), 2 * PAGE_SIZE);
+
+        nvmc.erase(PAGE_SIZE as u32, 2 * PAGE_SIZE as u32).unwrap();
+        assert_eq!(finish(&mut nvmc), [Operation::Erase(PAGE_SIZE)]);
+
+        let bytes = read_all(&mut nvmc);
+        assert!(bytes[..PAGE_SIZE].iter().all(|&b| b == 0));
+        assert!(bytes[PAGE_SIZE..].iter().all(|&b| b == 0xFF));
+
+        nvmc.erase(0, 2 * PAGE_SIZE as u32).unwrap();
+        assert_eq!(
+            finish(&mut nvmc),
+            [Operation::Erase(0), Operation::Erase(PAGE_SIZE)]
+        );
+        assert!(read_all(&mut nvmc).iter().all(|&b| b == 0xFF));
+
+        // An empty range does nothing
+        nvmc.erase(PAGE_SIZE as u32, PAGE_SIZE as u32).unwrap();
+        assert_eq!(finish(&mut nvmc), []);
+    }
+
+    #[test]
+    fn write() {
+        let mut nvmc = nvmc();
+        nvmc.erase(0, PAGE_SIZE as u32).unwrap();
+        finish(&mut nvmc);
+
+        nvmc.write(8, &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12])
+            .unwrap();
+        assert_eq!(
+            finish(&mut nvmc),
+            [
+                Operation::Write(8, 0x0403_0201),
+                Operation::Write(12, 0x0807_0605),
+                Operation::Write(16, 0x0C0B_0A09),
+            ]
+        );
+
+        let mut bytes = [0; 16];
+        nvmc.read(6, &mut bytes).unwrap();
+        assert_eq!(
+            bytes,
+            [0xFF, 0xFF, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 0xFF, 0xFF]
+        );
+
+        // Writing can only clear bits
+        nvmc.write(8, &[0xF0, 0x0F, 0xFF, 0x00]).unwrap();
+        finish(&mut nvmc);
+        nvmc.read(8, &mut bytes[..4]).unwrap();
+        assert_eq!(bytes[..4], [0x00, 0x02, 0x03, 0x00]);
+    }
+
+    #[test]
+    fn not_aligned() {
+        let mut nvmc = nvmc();
+        let page = PAGE_SIZE as u32;
+
+        assert!(matches!(nvmc.write(2, &[0; 4]), Err(Error::NotAligned)));
+        assert!(matches!(nvmc.write(4, &[0; 6]), Err(Error::NotAligned)));
+        assert!(matches!(nvmc.erase(4, page), Err(Error::NotAligned)));
+        assert!(matches!(nvmc.erase(0, page + 4), Err(Error::NotAligned)));
+
+        // Reading has no alignment requirements
+        let mut bytes = [0xAA; 3];
+        nvmc.read(1, &mut bytes).unwrap();
+        assert_eq!(bytes, [0; 3]);
+
+        assert_eq!(finish(&mut nvmc), []);
+    }
+
+    #[test]
+    fn out_of_bounds() {
+        let mut nvmc = nvmc();
+        let capacity = nvmc.capacity() as u32;
+        let page = PAGE_SIZE as u32;
+
+        let mut bytes = [0; 8];
+        assert!(matches!(
+            nvmc.read(capacity - 4, &mut bytes),
+            Err(Error::OutOfBounds)
+        ));
+        assert!(matches!(
+            nvmc.read(u32::max_value(), &mut bytes),
+            Err(Error::OutOfBounds)
+        ));
+        assert!(matches!(
+            nvmc.write(capacity, &[0; 4]),
+            Err(Error::OutOfBounds)
+        ));
+        assert!(matches!(
+            nvmc.erase(page, 3 * page),
+            Err(Error::OutOfBounds)
+        ));
+        assert!(matches!(nvmc.erase(page, 0), Err(Error::OutOfBounds)));
+
+        nvmc.read(capacity - 8, &mut bytes).unwrap();
+        assert_eq!(finish(&mut nvmc), []);
+    }
+}
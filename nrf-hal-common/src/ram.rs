@@ -0,0 +1,283 @@
+//! HAL interface for RAM section power and retention control
+//!
+//! See the "RAM power control" part of the POWER chapter in the product
+//! specification.
+//!
+//! The data RAM is split into blocks (one AHB slave each), which are further
+//! split into sections. Each section can be powered down while the chip is in
+//! System ON, and each section can be retained while it is powered down. All
+//! sections are powered down in System OFF, so only retained sections keep
+//! their contents across System OFF.
+use crate::target::POWER;
+
+/// Start address of the data RAM
+pub const RAM_START: usize = 0x2000_0000;
+
+/// Number of RAM blocks (`RAM[n]` register sets)
+#[cfg(feature = "52810")]
+pub const RAM_BLOCKS: usize = 3;
+
+/// Number of RAM blocks (`RAM[n]` register sets)
+#[cfg(feature = "52832")]
+pub const RAM_BLOCKS: usize = 8;
+
+/// Number of RAM blocks (`RAM[n]` register sets)
+#[cfg(feature = "52840")]
+pub const RAM_BLOCKS: usize = 9;
+
+/// Layout of each RAM block: (number of sections, section size in bytes)
+#[cfg(feature = "52810")]
+const LAYOUT: [(usize, usize); RAM_BLOCKS] = [(2, 0x1000), (2, 0x1000), (2, 0x1000)];
+
+/// Layout of each RAM block: (number of sections, section size in bytes)
+#[cfg(feature = "52832")]
+const LAYOUT: [(usize, usize); RAM_BLOCKS] = [
+    (2, 0x1000),
+    (2, 0x1000),
+    (2, 0x1000),
+    (2, 0x1000),
+    (2, 0x1000),
+    (2, 0x1000),
+    (2, 0x1000),
+    (2, 0x1000),
+];
+
+/// Layout of each RAM block: (number of sections, section size in bytes)
+#[cfg(feature = "52840")]
+const LAYOUT: [(usize, usize); RAM_BLOCKS] = [
+    (2, 0x1000),
+    (2, 0x1000),
+    (2, 0x1000),
+    (2, 0x1000),
+    (2, 0x1000),
+    (2, 0x1000),
+    (2, 0x1000),
+    (2, 0x1000),
+    (6, 0x8000),
+];
+
+/// A single RAM section, `RAM[block].S[section]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Section {
+    block: usize,
+    section: usize,
+}
+
+impl Section {
+    /// Returns the section `section` of RAM block `block`, or `None` if the
+    /// chip has no such section
+    pub fn new(block: usize, section: usize) -> Option<Self> {
+        if block < RAM_BLOCKS && section < LAYOUT[block].0 {
+            Some(Section { block, section })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the section that contains `address`, or `None` if the address
+    /// is outside of the data RAM
+    pub fn containing(address: usize) -> Option<Self> {
+        let mut start = RAM_START;
+        for (block, &(count, size)) in LAYOUT.iter().enumerate() {
+            let end = start + count * size;
+            if address >= start && address < end {
+                return Some(Section {
+                    block,
+                    section: (address - start) / size,
+                });
+            }
+            start = end;
+        }
+        None
+    }
+
+    /// The RAM block this section belongs to
+    pub fn block(&self) -> usize {
+        self.block
+    }
+
+    /// The index of this section within its RAM block
+    pub fn section(&self) -> usize {
+        self.section
+    }
+
+    /// Start address of this section
+    pub fn start(&self) -> usize {
+        let preceding: usize = LAYOUT[..self.block]
+            .iter()
+            .map(|&(count, size)| count * size)
+            .sum();
+        RAM_START + preceding + self.section * self.size()
+    }
+
+    /// Size of this section in bytes
+    pub fn size(&self) -> usize {
+        LAYOUT[self.block].1
+    }
+}
+
+/// A set of RAM sections, stored as one bit mask per RAM block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sections([u16; RAM_BLOCKS]);
+
+impl Sections {
+    /// The empty set
+    pub fn empty() -> Self {
+        Sections([0; RAM_BLOCKS])
+    }
+
+    /// All RAM sections of the chip
+    pub fn all() -> Self {
+        let mut masks = [0; RAM_BLOCKS];
+        for (mask, &(count, _)) in masks.iter_mut().zip(LAYOUT.iter()) {
+            *mask = ((1u32 << count) - 1) as u16;
+        }
+        Sections(masks)
+    }
+
+    /// All sections that overlap the address range `start..end`
+    ///
+    /// This is intended to be used with linker symbols, e.g. to find the
+    /// sections that hold a `.noinit` region that should be retained in
+    /// System OFF.
+    pub fn covering(start: usize, end: usize) -> Self {
+        let mut sections = Self::empty();
+        let mut section_start = RAM_START;
+        for (block, &(count, size)) in LAYOUT.iter().enumerate() {
+            for section in 0..count {
+                let section_end = section_start + size;
+                if start < section_end && end > section_start {
+                    sections.insert(Section { block, section });
+                }
+                section_start = section_end;
+            }
+        }
+        sections
+    }
+
+    /// Add a section to the set
+    pub fn insert(&mut self, section: Section) {
+        self.0[section.block] |= 1 << section.section;
+    }
+
+    /// Remove a section from the set
+    pub fn remove(&mut self, section: Section) {
+        self.0[section.block] &= !(1 << section.section);
+    }
+
+    /// Is the section part of this set?
+    pub fn contains(&self, section: Section) -> bool {
+        self.0[section.block] & (1 << section.section) != 0
+    }
+
+    /// Is the set empty?
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&mask| mask == 0)
+    }
+
+    /// The bit mask of sections within RAM block `block`
+    pub fn block_mask(&self, block: usize) -> u16 {
+        self.0[block]
+    }
+}
+
+impl From<Section> for Sections {
+    fn from(section: Section) -> Self {
+        let mut sections = Self::empty();
+        sections.insert(section);
+        sections
+    }
+}
+
+/// Interface to the RAM power control registers of the POWER peripheral
+///
+/// Bits `0..16` of `RAM[n].POWER` control whether a section is on in System
+/// ON, bits `16..32` control whether the section is retained while it is off.
+pub struct Ram(POWER);
+
+impl Ram {
+    /// Construct a new `Ram` from `pac::POWER`
+    pub fn new(power: POWER) -> Self {
+        Self(power)
+    }
+
+    /// Release the `pac::POWER` instance back
+    pub fn free(self) -> POWER {
+        self.0
+    }
+
+    /// Power on the given sections in System ON
+    pub fn power_on(&mut self, sections: &Sections) {
+        self.set(sections, 0);
+    }
+
+    /// Power down the given sections in System ON
+    ///
+    /// Unless retention is enabled for a section, its contents are lost. Make
+    /// sure that no section used by the stack or by static data is powered
+    /// down.
+    pub fn power_off(&mut self, sections: &Sections) {
+        self.clear(sections, 0);
+    }
+
+    /// Keep the contents of the given sections while they are powered down,
+    /// including in System OFF
+    pub fn enable_retention(&mut self, sections: &Sections) {
+        self.set(sections, 16);
+    }
+
+    /// Stop retaining the contents of the given sections while they are
+    /// powered down
+    pub fn disable_retention(&mut self, sections: &Sections) {
+        self.clear(sections, 16);
+    }
+
+    /// Is the section powered on in System ON?
+    pub fn is_powered(&self, section: Section) -> bool {
+        self.0.ram[section.block].power.read().bits() & (1 << section.section) != 0
+    }
+
+    /// Is the section retained while it is powered down?
+    pub fn is_retained(&self, section: Section) -> bool {
+        self.0.ram[section.block].power.read().bits() & (1 << (16 + section.section)) != 0
+    }
+
+    /// Sections that are powered on in System ON
+    pub fn powered(&self) -> Sections {
+        self.masks(0)
+    }
+
+    /// Sections that are retained while powered down
+    pub fn retained(&self) -> Sections {
+        self.masks(16)
+    }
+
+    fn set(&mut self, sections: &Sections, shift: u32) {
+        for (ram, &mask) in self.0.ram.iter().zip(sections.0.iter()) {
+            if mask != 0 {
+                // POWERSET only affects bits written as `1`
+                ram.powerset
+                    .write(|w| unsafe { w.bits(u32::from(mask) << shift) });
+            }
+        }
+    }
+
+    fn clear(&mut self, sections: &Sections, shift: u32) {
+        for (ram, &mask) in self.0.ram.iter().zip(sections.0.iter()) {
+            if mask != 0 {
+                // POWERCLR only affects bits written as `1`
+                ram.powerclr
+                    .write(|w| unsafe { w.bits(u32::from(mask) << shift) });
+            }
+        }
+    }
+
+    fn masks(&self, shift: u32) -> Sections {
+        let all = Sections::all();
+        let mut sections = Sections::empty();
+        for (block, ram) in self.0.ram.iter().take(RAM_BLOCKS).enumerate() {
+            sections.0[block] = (ram.power.read().bits() >> shift) as u16 & all.0[block];
+        }
+        sections
+    }
+}
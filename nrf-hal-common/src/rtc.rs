@@ -37,6 +37,7 @@ where
 }
 
 /// Interrupts/Events that can be generated by the RTCn peripheral
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RtcInterrupt {
     Tick,
     Overflow,
@@ -47,6 +48,7 @@ pub enum RtcInterrupt {
 }
 
 /// Compare registers available on the RTCn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RtcCompareReg {
     Compare0,
     Compare1,
@@ -54,6 +56,18 @@ pub enum RtcCompareReg {
     Compare3,
 }
 
+impl From<RtcCompareReg> for RtcInterrupt {
+    /// The event generated by a compare register
+    fn from(reg: RtcCompareReg) -> Self {
+        match reg {
+            RtcCompareReg::Compare0 => RtcInterrupt::Compare0,
+            RtcCompareReg::Compare1 => RtcInterrupt::Compare1,
+            RtcCompareReg::Compare2 => RtcInterrupt::Compare2,
+            RtcCompareReg::Compare3 => RtcInterrupt::Compare3,
+        }
+    }
+}
+
 impl<T, M> Rtc<T, M>
 where
     T: Instance,
@@ -203,6 +217,12 @@ where
         self.periph.counter.read().bits()
     }
 
+    /// Address of the counter register, for reading the counter without
+    /// access to the `Rtc`
+    pub(crate) fn counter_ptr(&self) -> *const u32 {
+        &self.periph.counter as *const _ as *const u32
+    }
+
     /// Destructure the high level interface. Does not reset any configuration made
     /// to the given RTC peripheral
     pub fn release(self) -> T {
@@ -6,12 +6,9 @@ use crate::target::{saadc_ns as saadc, SAADC_NS as SAADC};
 #[cfg(not(feature = "9160"))]
 use crate::target::{saadc, SAADC};
 
-use crate::gpio::{Floating, Input};
-use core::{
-    hint::unreachable_unchecked,
-    sync::atomic::{compiler_fence, Ordering::SeqCst},
-};
+use core::sync::atomic::{compiler_fence, Ordering::SeqCst};
 use embedded_hal::adc::{Channel, OneShot};
+use fpa::I16F16;
 
 pub use saadc::{
     ch::config::{GAIN_A as Gain, REFSEL_A as Reference, RESP_A as Resistor, TACQ_A as Time},
@@ -19,10 +16,17 @@ pub use saadc::{
     resolution::VAL_A as Resolution,
 };
 
-// Only 1 channel is allowed right now, a discussion needs to be had as to how
-// multiple channels should work (See "scan mode" in the datasheet)
-// Issue: https://github.com/nrf-rs/nrf-hal/issues/82
+/// Number of SAADC channels
+pub const CHANNELS: usize = 8;
 
+/// Interface to the SAADC peripheral
+///
+/// The `OneShot` implementation converts a single input on channel 0, using
+/// the channel settings from `SaadcConfig`. It requires all other channels to
+/// be disabled.
+///
+/// Multiple channels can be converted at once in "scan mode": configure them
+/// with `configure_channel`, then convert all of them with `scan`.
 pub struct Saadc(SAADC);
 
 impl Saadc {
@@ -57,57 +61,181 @@ impl Saadc {
         });
         saadc.ch[0].pseln.write(|w| w.pseln().nc());
 
-        // Calibrate
-        saadc.tasks_calibrateoffset.write(|w| unsafe { w.bits(1) });
-        while saadc.events_calibratedone.read().bits() == 0 {}
+        let mut saadc = Saadc(saadc);
+        saadc.calibrate();
+        saadc
+    }
 
-        Saadc(saadc)
+    /// Run the offset calibration and block until it has finished
+    ///
+    /// The offset drifts with temperature, so calibration should be repeated
+    /// when the temperature has changed by more than about 10 °C. It must not
+    /// be run while a conversion is in progress.
+    pub fn calibrate(&mut self) {
+        self.0.events_calibratedone.reset();
+        self.0.tasks_calibrateoffset.write(|w| unsafe { w.bits(1) });
+        while self.0.events_calibratedone.read().bits() == 0 {}
+        self.0.events_calibratedone.reset();
     }
-}
 
-pub struct SaadcConfig {
-    pub resolution: Resolution,
-    pub oversample: Oversample,
-    pub reference: Reference,
-    pub gain: Gain,
-    pub resistor: Resistor,
-    pub time: Time,
-}
+    /// The scaling of results of channel `n` with its current settings
+    pub fn scaling(&self, n: usize) -> Scaling {
+        assert!(n < CHANNELS);
 
-// 0 volts reads as 0, VDD volts reads as u16::MAX
-impl Default for SaadcConfig {
-    fn default() -> Self {
-        SaadcConfig {
-            resolution: Resolution::_14BIT,
-            oversample: Oversample::OVER8X,
-            reference: Reference::VDD1_4,
-            gain: Gain::GAIN1_4,
-            resistor: Resistor::BYPASS,
-            time: Time::_20US,
+        let config = self.0.ch[n].config.read().bits();
+        Scaling {
+            gain: (config >> 8) as u8 & 0x7,
+            internal_reference: config & (1 << 12) == 0,
+            resolution: 8 + 2 * (self.0.resolution.read().bits() as u8 & 0x7),
+            differential: config & (1 << 20) != 0,
         }
     }
-}
 
-impl<PIN> OneShot<Saadc, i16, PIN> for Saadc
-where
-    PIN: Channel<Saadc, ID = u8>,
-{
-    type Error = ();
-    fn read(&mut self, _pin: &mut PIN) -> nb::Result<i16, Self::Error> {
-        match PIN::channel() {
-            0 => self.0.ch[0].pselp.write(|w| w.pselp().analog_input0()),
-            1 => self.0.ch[0].pselp.write(|w| w.pselp().analog_input1()),
-            2 => self.0.ch[0].pselp.write(|w| w.pselp().analog_input2()),
-            3 => self.0.ch[0].pselp.write(|w| w.pselp().analog_input3()),
-            4 => self.0.ch[0].pselp.write(|w| w.pselp().analog_input4()),
-            5 => self.0.ch[0].pselp.write(|w| w.pselp().analog_input5()),
-            6 => self.0.ch[0].pselp.write(|w| w.pselp().analog_input6()),
-            7 => self.0.ch[0].pselp.write(|w| w.pselp().analog_input7()),
-            // This can never happen the only analog pins have already been defined
-            // PAY CLOSE ATTENTION TO ANY CHANGES TO THIS IMPL OR THE `channel_mappings!` MACRO
-            _ => unsafe { unreachable_unchecked() },
+    /// Configure channel `n` to convert `input` with its own settings
+    ///
+    /// Connecting an input enables the channel for `scan`. Channel 0 is also
+    /// used by the `OneShot` implementation, which will use the settings given
+    /// here from now on.
+    pub fn configure_channel(&mut self, n: usize, input: Input, config: ChannelConfig) {
+        self.configure(n, input, None, config);
+    }
+
+    /// Configure channel `n` to convert the difference between `positive`
+    /// and `negative`
+    ///
+    /// Results are signed, so the range of values is half of that in single
+    /// ended mode.
+    pub fn configure_differential_channel(
+        &mut self,
+        n: usize,
+        positive: Input,
+        negative: Input,
+        config: ChannelConfig,
+    ) {
+        self.configure(n, positive, Some(negative), config);
+    }
+
+    fn configure(
+        &mut self,
+        n: usize,
+        positive: Input,
+        negative: Option<Input>,
+        config: ChannelConfig,
+    ) {
+        assert!(n < CHANNELS);
+
+        let ChannelConfig {
+            reference,
+            gain,
+            resistor,
+            time,
+        } = config;
+
+        self.0.ch[n].config.write(|w| {
+            w.refsel().variant(reference);
+            w.gain().variant(gain);
+            w.tacq().variant(time);
+            w.mode().bit(negative.is_some());
+            w.resp().variant(resistor);
+            w.resn().bypass();
+            w.burst().enabled();
+            w
+        });
+        self.0.ch[n]
+            .pseln
+            .write(|w| unsafe { w.bits(negative.map_or(0, Input::bits)) });
+        self.0.ch[n]
+            .pselp
+            .write(|w| unsafe { w.bits(positive.bits()) });
+    }
+
+    /// Set the window of channel `n`
+    ///
+    /// The `LIMITH` event is generated when a result of the channel is above
+    /// `high`, the `LIMITL` event when it is below `low`.
+    pub fn set_limits(&mut self, n: usize, low: i16, high: i16) {
+        assert!(n < CHANNELS && low <= high);
+
+        let bits = u32::from(high as u16) << 16 | u32::from(low as u16);
+        self.0.ch[n].limit.write(|w| unsafe { w.bits(bits) });
+    }
+
+    /// Enable the interrupt for a limit event of channel `n`
+    ///
+    /// The interrupt also has to be unmasked in the NVIC, or the handler
+    /// won't get called.
+    pub fn enable_limit_interrupt(&mut self, n: usize, limit: Limit) {
+        self.0
+            .intenset
+            .write(|w| unsafe { w.bits(limit_interrupt_mask(n, limit)) });
+    }
+
+    /// Disable the interrupt for a limit event of channel `n`
+    pub fn disable_limit_interrupt(&mut self, n: usize, limit: Limit) {
+        self.0
+            .intenclr
+            .write(|w| unsafe { w.bits(limit_interrupt_mask(n, limit)) });
+    }
+
+    /// Obtain the state of a limit event of channel `n`, and optionally clear
+    /// the event if it is set
+    pub fn get_limit_event_triggered(
+        &mut self,
+        n: usize,
+        limit: Limit,
+        clear_on_read: bool,
+    ) -> bool {
+        let events = &self.0.events_ch[n];
+        match limit {
+            Limit::High => event_triggered!(events, limith, clear_on_read),
+            Limit::Low => event_triggered!(events, limitl, clear_on_read),
         }
+    }
 
+    /// Convert the difference between two inputs on channel 0
+    ///
+    /// This uses the channel 0 settings, just like the `OneShot`
+    /// implementation, and requires all other channels to be disabled. The
+    /// mode and inputs of channel 0 are restored afterwards.
+    pub fn read_differential<P, N>(
+        &mut self,
+        _positive: &mut P,
+        _negative: &mut N,
+    ) -> nb::Result<i16, ()>
+    where
+        P: Channel<Saadc, ID = u8>,
+        N: Channel<Saadc, ID = u8>,
+    {
+        let positive = Input::from_id(P::channel());
+        let negative = Input::from_id(N::channel());
+
+        let ch = &self.0.ch[0];
+        let (config, pseln, pselp) = (
+            ch.config.read().bits(),
+            ch.pseln.read().bits(),
+            ch.pselp.read().bits(),
+        );
+
+        self.0.ch[0].config.modify(|_, w| w.mode().diff());
+        self.0.ch[0]
+            .pseln
+            .write(|w| unsafe { w.bits(negative.bits()) });
+        self.0.ch[0]
+            .pselp
+            .write(|w| unsafe { w.bits(positive.bits()) });
+
+        let result = self.convert_single();
+
+        let ch = &self.0.ch[0];
+        ch.config.write(|w| unsafe { w.bits(config) });
+        ch.pseln.write(|w| unsafe { w.bits(pseln) });
+        ch.pselp.write(|w| unsafe { w.bits(pselp) });
+
+        result
+    }
+
+    /// Convert channel 0, which must be the only enabled channel
+    fn convert_single(&mut self) -> nb::Result<i16, ()> {
         let mut val: i16 = 0;
         self.0
             .result
@@ -138,6 +266,498 @@ where
 
         Ok(val)
     }
+
+    /// Disconnect the input of channel `n`, which excludes it from `scan`
+    pub fn disable_channel(&mut self, n: usize) {
+        assert!(n < CHANNELS);
+
+        self.0.ch[n].pselp.write(|w| w.pselp().nc());
+    }
+
+    /// Is an input connected to channel `n`?
+    pub fn is_channel_enabled(&self, n: usize) -> bool {
+        self.0.ch[n].pselp.read().bits() & 0x1F != 0
+    }
+
+    /// Convert all enabled channels with a single `SAMPLE` task
+    ///
+    /// The result of channel `n` is stored in `results[n]`. Entries of
+    /// disabled channels are left untouched.
+    pub fn scan(&mut self, results: &mut [i16; CHANNELS]) {
+        let mut enabled = [false; CHANNELS];
+        for (n, enabled) in enabled.iter_mut().enumerate() {
+            *enabled = self.is_channel_enabled(n);
+        }
+
+        let count = enabled.iter().filter(|&&e| e).count();
+        if count == 0 {
+            return;
+        }
+
+        // In scan mode, results are stored in the order of the enabled
+        // channels, lowest channel first
+        let mut buffer = [0i16; CHANNELS];
+        self.0
+            .result
+            .ptr
+            .write(|w| unsafe { w.ptr().bits(buffer.as_mut_ptr() as u32) });
+        self.0
+            .result
+            .maxcnt
+            .write(|w| unsafe { w.maxcnt().bits(count as _) });
+
+        // Conservative compiler fence to prevent starting the ADC before the
+        // pointer and maxcount have been set
+        compiler_fence(SeqCst);
+
+        self.0.tasks_start.write(|w| unsafe { w.bits(1) });
+        self.0.tasks_sample.write(|w| unsafe { w.bits(1) });
+
+        while self.0.events_end.read().bits() == 0 {}
+        self.0.events_end.reset();
+
+        // Second fence to prevent optimizations creating issues with the EasyDMA-modified `buffer`
+        compiler_fence(SeqCst);
+
+        let mut samples = buffer.iter();
+        for (result, &enabled) in results.iter_mut().zip(enabled.iter()) {
+            if enabled {
+                *result = *samples.next().unwrap();
+            }
+        }
+    }
+
+    /// Switch to continuous sampling into two alternating buffers
+    ///
+    /// Every enabled channel is converted on each sample trigger. The buffers
+    /// must have the same length, which should be a multiple of the number of
+    /// enabled channels. The internal timer (`SampleTrigger::Timer`) can only
+    /// be used with a single enabled channel, otherwise this panics.
+    ///
+    /// Sampling starts with `Continuous::start`.
+    pub fn into_continuous(
+        self,
+        buffers: [&'static mut [i16]; 2],
+        trigger: SampleTrigger,
+    ) -> Continuous {
+        assert!(!buffers[0].is_empty() && buffers[0].len() == buffers[1].len());
+        assert!(buffers[0].len() <= 0x7FFF); // MAXCNT is 15 bits wide
+
+        match trigger {
+            SampleTrigger::Timer(cc) => {
+                assert!((80..=2047).contains(&cc));
+                let enabled = (0..CHANNELS)
+                    .filter(|&n| self.is_channel_enabled(n))
+                    .count();
+                assert!(enabled == 1);
+                self.0
+                    .samplerate
+                    .write(|w| unsafe { w.cc().bits(cc) }.mode().timers());
+            }
+            SampleTrigger::Task => self.0.samplerate.write(|w| w.mode().task()),
+        }
+
+        Continuous {
+            saadc: self,
+            buffers,
+            filling: 0,
+        }
+    }
+}
+
+/// What triggers a sample in continuous mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleTrigger {
+    /// The internal timer, sampling at 16 MHz / `cc`, with `cc` in 80..=2047
+    Timer(u16),
+    /// The `SAMPLE` task, usually triggered by a TIMER or RTC event through
+    /// PPI (see `Continuous::task_sample`)
+    Task,
+}
+
+/// Continuous sampling into two alternating EasyDMA buffers
+///
+/// While the SAADC fills one buffer, the other one is handed to the
+/// application. `RESULT.PTR` is latched on the `STARTED` event, so the pointer
+/// to the next buffer is written right after each start, and the `END_START`
+/// short restarts the conversion without a gap when a buffer is full.
+///
+/// A completed buffer can be read until the other buffer is full. If the
+/// application takes longer than that, samples are overwritten.
+pub struct Continuous {
+    saadc: Saadc,
+    buffers: [&'static mut [i16]; 2],
+    /// Index of the buffer that the SAADC is currently filling
+    filling: usize,
+}
+
+impl Continuous {
+    /// Start sampling into the first buffer
+    pub fn start(&mut self) {
+        self.filling = 0;
+        self.set_buffer(0);
+
+        let saadc = &(self.saadc).0;
+
+        saadc.events_started.reset();
+        saadc.events_end.reset();
+
+        // Conservative compiler fence to prevent starting the ADC before the
+        // pointer and maxcount have been set
+        compiler_fence(SeqCst);
+
+        saadc.shorts.write(|w| w.end_start().enabled());
+        saadc.tasks_start.write(|w| unsafe { w.bits(1) });
+
+        // With the internal timer, sampling begins as soon as `SAMPLE` has
+        // been triggered once
+        if saadc.samplerate.read().mode().is_timers() {
+            saadc.tasks_sample.write(|w| unsafe { w.bits(1) });
+        }
+    }
+
+    /// Stop sampling
+    ///
+    /// The buffer that was being filled is discarded.
+    pub fn stop(&mut self) {
+        let saadc = &(self.saadc).0;
+
+        saadc.shorts.reset();
+        saadc.events_stopped.reset();
+        saadc.tasks_stop.write(|w| unsafe { w.bits(1) });
+        while saadc.events_stopped.read().bits() == 0 {}
+        saadc.events_stopped.reset();
+
+        saadc.events_started.reset();
+        saadc.events_end.reset();
+
+        // Conservative compiler fence to prevent optimizations that do not
+        // take in to account actions by DMA
+        compiler_fence(SeqCst);
+    }
+
+    /// Handle the `STARTED` and `END` events
+    ///
+    /// Call this from the SAADC interrupt handler, or poll it. Returns the
+    /// samples of a buffer that has just been filled completely.
+    pub fn poll(&mut self) -> Option<&[i16]> {
+        let mut completed = None;
+
+        // `END` is always followed by `STARTED` through the short, and both can
+        // occur while this runs. Looking at `STARTED` first means that a
+        // `STARTED` seen here never belongs to an `END` that is missed below.
+        // A `STARTED` that occurs after this read is handled by the next call.
+        let started = self.saadc.0.events_started.read().bits() != 0;
+        if started {
+            self.saadc.0.events_started.reset();
+        }
+
+        if self.saadc.0.events_end.read().bits() != 0 {
+            self.saadc.0.events_end.reset();
+            completed = Some(self.filling);
+            self.filling ^= 1;
+        }
+
+        if started {
+            // The current pointer has been latched, queue the other buffer
+            self.set_buffer(self.filling ^ 1);
+        }
+
+        // Prevent reading the buffer before the END event has been observed
+        compiler_fence(SeqCst);
+
+        match completed {
+            Some(index) => Some(&self.buffers[index][..]),
+            None => None,
+        }
+    }
+
+    /// Enable the `STARTED` and `END` interrupts
+    ///
+    /// The interrupt also has to be unmasked in the NVIC, or the handler
+    /// won't get called.
+    pub fn enable_interrupt(&mut self) {
+        self.saadc
+            .0
+            .intenset
+            .write(|w| w.started().set().end().set());
+    }
+
+    /// Disable the `STARTED` and `END` interrupts
+    pub fn disable_interrupt(&mut self) {
+        self.saadc
+            .0
+            .intenclr
+            .write(|w| w.started().clear().end().clear());
+    }
+
+    /// Obtain the state of a limit event of channel `n`, and optionally clear
+    /// the event if it is set
+    ///
+    /// Limits and their interrupts are set up on the `Saadc` before switching
+    /// to continuous mode.
+    pub fn get_limit_event_triggered(
+        &mut self,
+        n: usize,
+        limit: Limit,
+        clear_on_read: bool,
+    ) -> bool {
+        self.saadc
+            .get_limit_event_triggered(n, limit, clear_on_read)
+    }
+
+    /// The `SAMPLE` task, to be connected to an event through PPI
+    pub fn task_sample(&self) -> &saadc::TASKS_SAMPLE {
+        &self.saadc.0.tasks_sample
+    }
+
+    /// Stop sampling and return the `Saadc` and the buffers
+    pub fn free(mut self) -> (Saadc, [&'static mut [i16]; 2]) {
+        self.stop();
+        self.saadc.0.samplerate.write(|w| w.mode().task());
+
+        (self.saadc, self.buffers)
+    }
+
+    fn set_buffer(&mut self, index: usize) {
+        let buffer = &mut self.buffers[index];
+        let saadc = &(self.saadc).0;
+
+        saadc
+            .result
+            .ptr
+            .write(|w| unsafe { w.ptr().bits(buffer.as_mut_ptr() as u32) });
+        saadc
+            .result
+            .maxcnt
+            .write(|w| unsafe { w.maxcnt().bits(buffer.len() as _) });
+    }
+}
+
+/// Limit events of a SAADC channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    /// `LIMITH`, a result was above the high limit
+    High,
+    /// `LIMITL`, a result was below the low limit
+    Low,
+}
+
+/// Bit of the limit event of channel `n` in `INTEN`
+fn limit_interrupt_mask(n: usize, limit: Limit) -> u32 {
+    assert!(n < CHANNELS);
+
+    // CH[0].LIMITH is bit 6, followed by CH[0].LIMITL and the other channels
+    match limit {
+        Limit::High => 1 << (6 + 2 * n),
+        Limit::Low => 1 << (7 + 2 * n),
+    }
+}
+
+/// An input that can be connected to a SAADC channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    /// Analog input pin `AIN0` to `AIN7`
+    Analog(u8),
+    /// The supply voltage VDD
+    Vdd,
+    /// The high voltage supply VDDH, divided by 5
+    #[cfg(feature = "52840")]
+    VddhDiv5,
+}
+
+impl Input {
+    /// The analog input of `pin`
+    pub fn pin<PIN>(_pin: &PIN) -> Self
+    where
+        PIN: Channel<Saadc, ID = u8>,
+    {
+        Input::from_id(PIN::channel())
+    }
+
+    /// The input of a `Channel<Saadc>` implementation
+    fn from_id(id: u8) -> Self {
+        match id {
+            0..=7 => Input::Analog(id),
+            8 => Input::Vdd,
+            #[cfg(feature = "52840")]
+            9 => Input::VddhDiv5,
+            // `Channel` can also be implemented outside of this crate, but
+            // no such implementation is a valid input
+            _ => unreachable!(),
+        }
+    }
+
+    /// Value of the `PSELP`/`PSELN` registers for this input
+    fn bits(self) -> u32 {
+        match self {
+            Input::Analog(n) => {
+                assert!(n < 8);
+                u32::from(n) + 1
+            }
+            Input::Vdd => 9,
+            #[cfg(feature = "52840")]
+            Input::VddhDiv5 => 0x0D,
+        }
+    }
+}
+
+/// Conversion of raw results to voltages
+///
+/// A result is `V(P) - V(N)` scaled by `GAIN / REFERENCE * 2^(RESOLUTION - m)`,
+/// where `m` is 1 in differential mode and 0 otherwise. Oversampling averages
+/// the samples and doesn't change the scaling.
+///
+/// The `VDD1_4` reference depends on the supply voltage, which has to be
+/// passed to the conversion methods. It is ignored with the internal
+/// reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scaling {
+    /// Value of the `GAIN` field
+    gain: u8,
+    internal_reference: bool,
+    /// Resolution in bits
+    resolution: u8,
+    differential: bool,
+}
+
+impl Scaling {
+    /// The scaling for the given settings
+    pub fn new(
+        gain: &Gain,
+        reference: &Reference,
+        resolution: &Resolution,
+        differential: bool,
+    ) -> Self {
+        Scaling {
+            gain: match gain {
+                Gain::GAIN1_6 => 0,
+                Gain::GAIN1_5 => 1,
+                Gain::GAIN1_4 => 2,
+                Gain::GAIN1_3 => 3,
+                Gain::GAIN1_2 => 4,
+                Gain::GAIN1 => 5,
+                Gain::GAIN2 => 6,
+                Gain::GAIN4 => 7,
+            },
+            internal_reference: match reference {
+                Reference::INTERNAL => true,
+                Reference::VDD1_4 => false,
+            },
+            resolution: match resolution {
+                Resolution::_8BIT => 8,
+                Resolution::_10BIT => 10,
+                Resolution::_12BIT => 12,
+                Resolution::_14BIT => 14,
+            },
+            differential,
+        }
+    }
+
+    /// Convert `raw` to microvolts
+    pub fn to_microvolts(&self, raw: i16, vdd_mv: u32) -> i32 {
+        // The gain as a fraction `numerator / denominator`
+        let (numerator, denominator): (i64, i64) = match self.gain {
+            0 => (1, 6),
+            1 => (1, 5),
+            2 => (1, 4),
+            3 => (1, 3),
+            4 => (1, 2),
+            5 => (1, 1),
+            6 => (2, 1),
+            _ => (4, 1),
+        };
+        let reference_uv = if self.internal_reference {
+            600_000
+        } else {
+            i64::from(vdd_mv) * 1000 / 4
+        };
+        let shift = self.resolution - self.differential as u8;
+
+        let dividend = i64::from(raw) * reference_uv * denominator;
+        let divisor = numerator << shift;
+        // Round to nearest, away from zero on ties
+        let rounded = if dividend < 0 {
+            (dividend - divisor / 2) / divisor
+        } else {
+            (dividend + divisor / 2) / divisor
+        };
+        rounded as i32
+    }
+
+    /// Convert `raw` to millivolts
+    pub fn to_millivolts(&self, raw: i16, vdd_mv: u32) -> i32 {
+        let uv = self.to_microvolts(raw, vdd_mv);
+        if uv < 0 {
+            (uv - 500) / 1000
+        } else {
+            (uv + 500) / 1000
+        }
+    }
+
+    /// Convert `raw` to volts
+    pub fn to_volts(&self, raw: i16, vdd_mv: u32) -> I16F16 {
+        let uv = i64::from(self.to_microvolts(raw, vdd_mv));
+        I16F16::from_bits(((uv << 16) / 1_000_000) as i32)
+    }
+}
+
+/// Settings of a single SAADC channel
+pub struct ChannelConfig {
+    pub reference: Reference,
+    pub gain: Gain,
+    pub resistor: Resistor,
+    pub time: Time,
+}
+
+// 0 volts reads as 0, VDD volts reads as the maximum value of the resolution
+impl Default for ChannelConfig {
+    fn default() -> Self {
+        ChannelConfig {
+            reference: Reference::VDD1_4,
+            gain: Gain::GAIN1_4,
+            resistor: Resistor::BYPASS,
+            time: Time::_20US,
+        }
+    }
+}
+
+pub struct SaadcConfig {
+    pub resolution: Resolution,
+    pub oversample: Oversample,
+    pub reference: Reference,
+    pub gain: Gain,
+    pub resistor: Resistor,
+    pub time: Time,
+}
+
+// 0 volts reads as 0, VDD volts reads as the maximum value of the resolution
+impl Default for SaadcConfig {
+    fn default() -> Self {
+        SaadcConfig {
+            resolution: Resolution::_14BIT,
+            oversample: Oversample::OVER8X,
+            reference: Reference::VDD1_4,
+            gain: Gain::GAIN1_4,
+            resistor: Resistor::BYPASS,
+            time: Time::_20US,
+        }
+    }
+}
+
+impl<PIN> OneShot<Saadc, i16, PIN> for Saadc
+where
+    PIN: Channel<Saadc, ID = u8>,
+{
+    type Error = ();
+    fn read(&mut self, _pin: &mut PIN) -> nb::Result<i16, Self::Error> {
+        let input = Input::from_id(PIN::channel());
+        self.0.ch[0]
+            .pselp
+            .write(|w| unsafe { w.bits(input.bits()) });
+
+        self.convert_single()
+    }
 }
 
 macro_rules! channel_mappings {
@@ -154,26 +774,146 @@ macro_rules! channel_mappings {
     };
 }
 
-#[cfg(feature = "9160")]
+analog_input_channels!(Saadc);
+
+/// The supply voltage VDD as a `Channel<Saadc>`
+pub struct InternalVdd;
+
+/// The high voltage supply VDDH divided by 5 as a `Channel<Saadc>`
+#[cfg(feature = "52840")]
+pub struct InternalVddHdiv5;
+
 channel_mappings! {
-    0 => crate::gpio::p0::P0_13<Input<Floating>>,
-    1 => crate::gpio::p0::P0_14<Input<Floating>>,
-    2 => crate::gpio::p0::P0_15<Input<Floating>>,
-    3 => crate::gpio::p0::P0_16<Input<Floating>>,
-    4 => crate::gpio::p0::P0_17<Input<Floating>>,
-    5 => crate::gpio::p0::P0_18<Input<Floating>>,
-    6 => crate::gpio::p0::P0_19<Input<Floating>>,
-    7 => crate::gpio::p0::P0_20<Input<Floating>>
+    8 => InternalVdd
 }
 
-#[cfg(not(feature = "9160"))]
+#[cfg(feature = "52840")]
 channel_mappings! {
-    0 => crate::gpio::p0::P0_02<Input<Floating>>,
-    1 => crate::gpio::p0::P0_03<Input<Floating>>,
-    2 => crate::gpio::p0::P0_04<Input<Floating>>,
-    3 => crate::gpio::p0::P0_05<Input<Floating>>,
-    4 => crate::gpio::p0::P0_28<Input<Floating>>,
-    5 => crate::gpio::p0::P0_29<Input<Floating>>,
-    6 => crate::gpio::p0::P0_30<Input<Floating>>,
-    7 => crate::gpio::p0::P0_31<Input<Floating>>
+    9 => InternalVddHdiv5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Gain, Reference, Resolution, Scaling};
+    use fpa::I16F16;
+
+    const GAINS: [Gain; 8] = [
+        Gain::GAIN1_6,
+        Gain::GAIN1_5,
+        Gain::GAIN1_4,
+        Gain::GAIN1_3,
+        Gain::GAIN1_2,
+        Gain::GAIN1,
+        Gain::GAIN2,
+        Gain::GAIN4,
+    ];
+
+    /// Full scale in microvolts for each gain, with the internal 0.6 V
+    /// reference and with `VDD1_4` at a VDD of 3 V
+    const FULL_SCALE_INTERNAL: [i32; 8] = [
+        3_600_000, 3_000_000, 2_400_000, 1_800_000, 1_200_000, 600_000, 300_000, 150_000,
+    ];
+    const FULL_SCALE_VDD: [i32; 8] = [
+        4_500_000, 3_750_000, 3_000_000, 2_250_000, 1_500_000, 750_000, 375_000, 187_500,
+    ];
+    const VDD_MV: u32 = 3000;
+
+    const RESOLUTIONS: [(Resolution, u8); 4] = [
+        (Resolution::_8BIT, 8),
+        (Resolution::_10BIT, 10),
+        (Resolution::_12BIT, 12),
+        (Resolution::_14BIT, 14),
+    ];
+
+    #[test]
+    fn full_scale_for_every_gain_and_reference() {
+        let references = [
+            (Reference::INTERNAL, FULL_SCALE_INTERNAL),
+            (Reference::VDD1_4, FULL_SCALE_VDD),
+        ];
+
+        for (reference, full_scales) in references.iter() {
+            for (gain, &full_scale) in GAINS.iter().zip(full_scales.iter()) {
+                for (resolution, bits) in RESOLUTIONS.iter() {
+                    for &differential in [false, true].iter() {
+                        let scaling = Scaling::new(gain, reference, resolution, differential);
+                        let one = 1i16 << (bits - differential as u8);
+
+                        assert_eq!(scaling.to_microvolts(0, VDD_MV), 0);
+                        assert_eq!(scaling.to_microvolts(one, VDD_MV), full_scale);
+                        assert_eq!(scaling.to_microvolts(one / 2, VDD_MV), full_scale / 2);
+                        assert_eq!(scaling.to_microvolts(-one / 2, VDD_MV), -full_scale / 2);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn vdd_is_ignored_with_internal_reference() {
+        let scaling = Scaling::new(
+            &Gain::GAIN1_6,
+            &Reference::INTERNAL,
+            &Resolution::_12BIT,
+            false,
+        );
+
+        assert_eq!(scaling.to_microvolts(2048, 1800), 1_800_000);
+        assert_eq!(scaling.to_microvolts(2048, 3600), 1_800_000);
+    }
+
+    #[test]
+    fn rounding_of_negative_results() {
+        // One LSB is 14062.5 uV, ties round away from zero
+        let scaling = Scaling::new(
+            &Gain::GAIN1_6,
+            &Reference::INTERNAL,
+            &Resolution::_8BIT,
+            true,
+        );
+        let scaling_se = Scaling::new(
+            &Gain::GAIN1_6,
+            &Reference::INTERNAL,
+            &Resolution::_8BIT,
+            false,
+        );
+        assert_eq!(scaling_se.to_microvolts(1, VDD_MV), 14_063);
+        assert_eq!(scaling_se.to_microvolts(-1, VDD_MV), -14_063);
+        assert_eq!(scaling.to_microvolts(-1, VDD_MV), -28_125);
+
+        // One LSB is 146.484375 uV
+        let scaling = Scaling::new(
+            &Gain::GAIN1,
+            &Reference::INTERNAL,
+            &Resolution::_12BIT,
+            false,
+        );
+        assert_eq!(scaling.to_microvolts(-1, VDD_MV), -146);
+        assert_eq!(scaling.to_microvolts(-3, VDD_MV), -439);
+
+        assert_eq!(scaling_se.to_millivolts(-1, VDD_MV), -14);
+        assert_eq!(scaling_se.to_millivolts(-36, VDD_MV), -506);
+    }
+
+    #[test]
+    fn full_scale_14_bit() {
+        let single_ended = Scaling::new(
+            &Gain::GAIN1_6,
+            &Reference::INTERNAL,
+            &Resolution::_14BIT,
+            false,
+        );
+        assert_eq!(single_ended.to_microvolts(16383, VDD_MV), 3_599_780);
+        assert_eq!(single_ended.to_millivolts(16383, VDD_MV), 3600);
+        assert!(single_ended.to_volts(16383, VDD_MV) == I16F16::from_bits(235_915));
+
+        let differential = Scaling::new(
+            &Gain::GAIN1_6,
+            &Reference::INTERNAL,
+            &Resolution::_14BIT,
+            true,
+        );
+        assert_eq!(differential.to_microvolts(8191, VDD_MV), 3_599_561);
+        assert_eq!(differential.to_microvolts(-8192, VDD_MV), -3_600_000);
+    }
 }
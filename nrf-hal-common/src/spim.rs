@@ -18,6 +18,9 @@ use core::iter::repeat_with;
 #[cfg(any(feature = "52832", feature = "52840"))]
 use crate::target::{SPIM1, SPIM2};
 
+#[cfg(feature = "9160")]
+use crate::target::{SPIM0_S, SPIM1_NS, SPIM1_S, SPIM2_NS, SPIM2_S, SPIM3_NS, SPIM3_S};
+
 use crate::gpio::{Floating, Input, Output, Pin, PushPull};
 use crate::target_constants::{EASY_DMA_SIZE, FORCE_COPY_BUFFER_SIZE};
 use crate::{slice_in_ram, slice_in_ram_or, DmaSlice};
@@ -409,3 +412,20 @@ impl Instance for SPIM1 {}
 
 #[cfg(any(feature = "52832", feature = "52840"))]
 impl Instance for SPIM2 {}
+
+// The secure aliases of the nRF9160 share the register layout of the
+// non-secure ones. Which of them is accessible depends on the SPU setup.
+#[cfg(feature = "9160")]
+impl Instance for SPIM0_S {}
+#[cfg(feature = "9160")]
+impl Instance for SPIM1_NS {}
+#[cfg(feature = "9160")]
+impl Instance for SPIM1_S {}
+#[cfg(feature = "9160")]
+impl Instance for SPIM2_NS {}
+#[cfg(feature = "9160")]
+impl Instance for SPIM2_S {}
+#[cfg(feature = "9160")]
+impl Instance for SPIM3_NS {}
+#[cfg(feature = "9160")]
+impl Instance for SPIM3_S {}
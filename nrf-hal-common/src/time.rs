@@ -16,6 +16,34 @@ pub struct KiloHertz(pub u32);
 #[derive(Clone, Copy)]
 pub struct MegaHertz(pub u32);
 
+/// Value of the UART and UARTE `BAUDRATE` register that comes closest to
+/// `baud`
+///
+/// The register holds `baud * 2^32 / 16 MHz`, of which only the upper 20 bits
+/// are used. The predefined values of the product specification differ from
+/// this for several standard rates, so use those where there is one.
+pub(crate) fn baudrate_register(baud: Bps) -> u32 {
+    assert!(baud.0 > 0 && baud.0 <= 1_000_000);
+
+    let exact = ((u64::from(baud.0) << 32) + CLOCK / 2) / CLOCK;
+    (((exact + 0x800) & 0xFFFF_F000) as u32).max(0x1000)
+}
+
+/// Baud rate that the UART and UARTE generate from the `BAUDRATE` register
+/// value `bits`
+///
+/// The clock is divided by `2^32 / bits`, rounded down. This gives the actual
+/// rates that the baud rate tables of the product specification list, e.g.
+/// 115108 Bd for the UARTE's `0x01D60000` and 115942 Bd for the UART's
+/// `0x01D7E000`.
+pub(crate) fn actual_baudrate(bits: u32) -> Bps {
+    let divider = (1 << 32) / u64::from(bits);
+    Bps(((CLOCK + divider / 2) / divider) as u32)
+}
+
+/// Clock of the UART and UARTE baud rate generator
+const CLOCK: u64 = 16_000_000;
+
 /// Extension trait that adds convenience methods to the `u32` type
 pub trait U32Ext {
     /// Wrap in `Bps`
@@ -3,10 +3,12 @@
 //! See product specification, chapter 24.
 
 #[cfg(feature = "9160")]
-use crate::target::{Interrupt, TIMER0_NS as TIMER0, TIMER1_NS as TIMER1, TIMER2_NS as TIMER2};
+use crate::target::{
+    timer0_ns as timer0, Interrupt, TIMER0_NS as TIMER0, TIMER1_NS as TIMER1, TIMER2_NS as TIMER2,
+};
 
 #[cfg(not(feature = "9160"))]
-use crate::target::{Interrupt, TIMER0, TIMER1, TIMER2};
+use crate::target::{timer0, Interrupt, TIMER0, TIMER1, TIMER2};
 
 use cast::u32;
 use embedded_hal::{
@@ -260,6 +262,13 @@ pub trait Instance {
     fn set_periodic(&self);
 
     fn set_oneshot(&self);
+
+    /// The registers of this instance, e.g. for connecting tasks and events
+    /// through PPI
+    ///
+    /// Instances with more capture/compare registers than `TIMER0` share its
+    /// layout for all registers that `TIMER0` has.
+    fn as_timer0(&self) -> &timer0::RegisterBlock;
 }
 
 macro_rules! impl_instance {
@@ -355,6 +364,12 @@ macro_rules! impl_instance {
                     );
                     self.bitmode.write(|w| w.bitmode()._32bit());
                 }
+
+                fn as_timer0(&self) -> &timer0::RegisterBlock {
+                    // The register block of this instance starts with the
+                    // same registers as that of `TIMER0`
+                    unsafe { &*($name::ptr() as *const timer0::RegisterBlock) }
+                }
             }
         )*
     }
@@ -16,10 +16,13 @@ use crate::target::{twim0, P0, TWIM0};
 #[cfg(any(feature = "52832", feature = "52840"))]
 use crate::target::TWIM1;
 
+#[cfg(feature = "9160")]
+use crate::target::{TWIM0_S, TWIM1_NS, TWIM1_S, TWIM2_NS, TWIM2_S, TWIM3_NS, TWIM3_S};
+
 use crate::{
     gpio::{Floating, Input, Pin},
-    slice_in_ram_or,
-    target_constants::EASY_DMA_SIZE,
+    slice_in_ram, slice_in_ram_or,
+    target_constants::{EASY_DMA_SIZE, FORCE_COPY_BUFFER_SIZE},
 };
 
 pub use twim0::frequency::FREQUENCY_A as Frequency;
@@ -90,7 +93,33 @@ where
     ///
     /// The buffer must have a length of at most 255 bytes on the nRF52832
     /// and at most 65535 bytes on the nRF52840.
+    ///
+    /// A buffer that is not in RAM, e.g. a constant command table, is copied
+    /// to the stack first. It must fit into `FORCE_COPY_BUFFER_SIZE` bytes,
+    /// as the write has to be a single transaction, or `TxBufferTooLong` is
+    /// returned.
     pub fn write(&mut self, address: u8, buffer: &[u8]) -> Result<(), Error> {
+        if slice_in_ram(buffer) {
+            self.write_dma(address, buffer)
+        } else {
+            self.write_copy(address, buffer)
+        }
+    }
+
+    /// Copy `buffer` to the stack and write it from there
+    fn write_copy(&mut self, address: u8, buffer: &[u8]) -> Result<(), Error> {
+        if buffer.len() > FORCE_COPY_BUFFER_SIZE {
+            return Err(Error::TxBufferTooLong);
+        }
+
+        let mut buf = [0u8; FORCE_COPY_BUFFER_SIZE];
+        buf[..buffer.len()].copy_from_slice(buffer);
+
+        self.write_dma(address, &buf[..buffer.len()])
+    }
+
+    /// Write a buffer that is in RAM to an I2C slave
+    fn write_dma(&mut self, address: u8, buffer: &[u8]) -> Result<(), Error> {
         slice_in_ram_or(buffer, Error::DMABufferNotInDataMemory)?;
 
         if buffer.len() > EASY_DMA_SIZE {
@@ -230,11 +259,45 @@ where
     /// triggering a stop condition between the two
     ///
     /// The buffer must have a length of at most 255 bytes.
+    ///
+    /// Like for `write`, a `wr_buffer` that is not in RAM is copied to the
+    /// stack first.
     pub fn write_then_read(
         &mut self,
         address: u8,
         wr_buffer: &[u8],
         rd_buffer: &mut [u8],
+    ) -> Result<(), Error> {
+        if slice_in_ram(wr_buffer) {
+            self.write_then_read_dma(address, wr_buffer, rd_buffer)
+        } else {
+            self.write_then_read_copy(address, wr_buffer, rd_buffer)
+        }
+    }
+
+    /// Copy `wr_buffer` to the stack and do the transaction from there
+    fn write_then_read_copy(
+        &mut self,
+        address: u8,
+        wr_buffer: &[u8],
+        rd_buffer: &mut [u8],
+    ) -> Result<(), Error> {
+        if wr_buffer.len() > FORCE_COPY_BUFFER_SIZE {
+            return Err(Error::TxBufferTooLong);
+        }
+
+        let mut buf = [0u8; FORCE_COPY_BUFFER_SIZE];
+        buf[..wr_buffer.len()].copy_from_slice(wr_buffer);
+
+        self.write_then_read_dma(address, &buf[..wr_buffer.len()], rd_buffer)
+    }
+
+    /// Write data from RAM to an I2C slave, then read data from the slave
+    fn write_then_read_dma(
+        &mut self,
+        address: u8,
+        wr_buffer: &[u8],
+        rd_buffer: &mut [u8],
     ) -> Result<(), Error> {
         // NOTE: RAM slice check for `rd_buffer` is not necessary, as a mutable
         // slice can only be built from data located in RAM
@@ -406,3 +469,20 @@ impl Instance for TWIM0 {}
 
 #[cfg(any(feature = "52832", feature = "52840"))]
 impl Instance for TWIM1 {}
+
+// The secure aliases of the nRF9160 share the register layout of the
+// non-secure ones. Which of them is accessible depends on the SPU setup.
+#[cfg(feature = "9160")]
+impl Instance for TWIM0_S {}
+#[cfg(feature = "9160")]
+impl Instance for TWIM1_NS {}
+#[cfg(feature = "9160")]
+impl Instance for TWIM1_S {}
+#[cfg(feature = "9160")]
+impl Instance for TWIM2_NS {}
+#[cfg(feature = "9160")]
+impl Instance for TWIM2_S {}
+#[cfg(feature = "9160")]
+impl Instance for TWIM3_NS {}
+#[cfg(feature = "9160")]
+impl Instance for TWIM3_S {}
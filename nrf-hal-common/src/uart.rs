@@ -8,6 +8,7 @@ use void::Void;
 
 use crate::gpio::{Floating, Input, Output, Pin, PushPull};
 use crate::target::{uart0, UART0};
+use crate::time::{actual_baudrate, baudrate_register, Bps};
 
 // Re-export SVD variants to allow user to directly set values
 pub use uart0::{baudrate::BAUDRATE_A as Baudrate, config::PARITY_A as Parity};
@@ -16,12 +17,29 @@ pub use uart0::{baudrate::BAUDRATE_A as Baudrate, config::PARITY_A as Parity};
 pub struct Uart<T>(T);
 
 #[derive(Debug)]
-pub enum Error {}
+pub enum Error {
+    /// A byte was received while the receive FIFO was full
+    Overrun,
+    /// A byte was received with the wrong parity
+    Parity,
+    /// A byte was received without a valid stop bit
+    Framing,
+    /// RXD was held low for longer than a whole frame
+    Break,
+    /// A byte was received while the receive buffer of `BufferedUart` was
+    /// full, and has been dropped
+    BufferOverrun,
+}
 
 impl<T> Uart<T>
 where
     T: Instance,
 {
+    /// Configure the UART
+    ///
+    /// Hardware flow control is enabled if both the `cts` and the `rts` pin
+    /// are given. The UART then stops sending while CTS is high, and raises
+    /// RTS while its receive FIFO is about to overflow.
     pub fn new(uart: T, pins: Pins, parity: Parity, baudrate: Baudrate) -> Self {
         // Fill register with dummy data to trigger txd event
         uart.txd.write(|w| unsafe { w.bits(0) });
@@ -69,12 +87,132 @@ where
         Uart(uart)
     }
 
+    /// Change the baud rate to one of the predefined values
+    pub fn set_baudrate(&mut self, baudrate: Baudrate) {
+        self.0.baudrate.write(|w| w.baudrate().variant(baudrate));
+    }
+
+    /// Change the baud rate to an arbitrary value
+    ///
+    /// The predefined value of the product specification is used for the
+    /// standard rates. The register only has a limited resolution, so the
+    /// baud rate that is actually generated is returned. It can be compared
+    /// against `baud` to get the error.
+    ///
+    /// `baud` must be at most 1 Mbaud.
+    pub fn set_bps(&mut self, baud: Bps) -> Bps {
+        let bits = match standard_baudrate(baud) {
+            Some(baudrate) => baudrate.into(),
+            None => baudrate_register(baud),
+        };
+        self.0.baudrate.write(|w| unsafe { w.bits(bits) });
+        actual_baudrate(bits)
+    }
+
+    /// Change the parity
+    pub fn set_parity(&mut self, parity: Parity) {
+        self.0.config.modify(|_, w| w.parity().variant(parity));
+    }
+
+    /// Enable or disable hardware flow control
+    ///
+    /// Flow control can only be enabled if both the `cts` and the `rts` pin
+    /// were passed to `new`.
+    pub fn set_hardware_flow_control(&mut self, enabled: bool) {
+        if enabled {
+            assert!(
+                self.0.pselcts.read().bits() != 0xFFFFFFFF
+                    && self.0.pselrts.read().bits() != 0xFFFFFFFF
+            );
+        }
+        self.0.config.modify(|_, w| w.hwfc().bit(enabled));
+    }
+
+    /// Receive and transmit through buffers that are serviced by the UART
+    /// interrupt
+    ///
+    /// See `BufferedUart` for how to use the result. The UART doesn't use
+    /// DMA, so the buffers can also live on the stack.
+    pub fn into_buffered<'a>(
+        self,
+        rx_buffer: &'a mut [u8],
+        tx_buffer: &'a mut [u8],
+    ) -> BufferedUart<'a, T> {
+        assert!(!rx_buffer.is_empty() && !tx_buffer.is_empty());
+
+        // Wait for the last byte to be sent, so that `TXDRDY` only signals
+        // bytes sent from the buffer
+        while self.0.events_txdrdy.read().bits() == 0 {}
+        self.0.events_txdrdy.reset();
+
+        BufferedUart {
+            uart: self,
+            rx: Queue::new(rx_buffer),
+            tx: Queue::new(tx_buffer),
+            tx_busy: false,
+            error: None,
+        }
+    }
+
     /// Return the raw interface to the underlying UARTE peripheral
     pub fn free(self) -> T {
         self.0
     }
 }
 
+/// The predefined `BAUDRATE` value for `baud`, if it is a standard rate
+fn standard_baudrate(baud: Bps) -> Option<Baudrate> {
+    Some(match baud.0 {
+        1200 => Baudrate::BAUD1200,
+        2400 => Baudrate::BAUD2400,
+        4800 => Baudrate::BAUD4800,
+        9600 => Baudrate::BAUD9600,
+        14400 => Baudrate::BAUD14400,
+        19200 => Baudrate::BAUD19200,
+        28800 => Baudrate::BAUD28800,
+        38400 => Baudrate::BAUD38400,
+        57600 => Baudrate::BAUD57600,
+        76800 => Baudrate::BAUD76800,
+        115200 => Baudrate::BAUD115200,
+        230400 => Baudrate::BAUD230400,
+        250000 => Baudrate::BAUD250000,
+        460800 => Baudrate::BAUD460800,
+        921600 => Baudrate::BAUD921600,
+        1_000_000 => Baudrate::BAUD1M,
+        _ => return None,
+    })
+}
+
+/// Return and clear the error that occurred during reception, if any
+///
+/// If several errors occurred, a break is reported before a framing error,
+/// and both before parity and overrun errors.
+fn take_error(uart: &uart0::RegisterBlock) -> Result<(), Error> {
+    if uart.events_error.read().bits() == 0 {
+        return Ok(());
+    }
+    uart.events_error.reset();
+
+    // The flags are cleared by writing `1` to them
+    let src = uart.errorsrc.read().bits();
+    uart.errorsrc.write(|w| unsafe { w.bits(src) });
+
+    if src & ERRORSRC_BREAK != 0 {
+        Err(Error::Break)
+    } else if src & ERRORSRC_FRAMING != 0 {
+        Err(Error::Framing)
+    } else if src & ERRORSRC_PARITY != 0 {
+        Err(Error::Parity)
+    } else {
+        Err(Error::Overrun)
+    }
+}
+
+/// Flags in `ERRORSRC`
+const ERRORSRC_PARITY: u32 = 1 << 1;
+const ERRORSRC_FRAMING: u32 = 1 << 2;
+const ERRORSRC_BREAK: u32 = 1 << 3;
+
 impl<T> embedded_hal::serial::Read<u8> for Uart<T>
 where
     T: Instance,
@@ -82,6 +220,8 @@ where
     type Error = Error;
 
     fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        take_error(&self.0)?;
+
         match self.0.events_rxdrdy.read().bits() {
             0 => Err(nb::Error::WouldBlock),
             _ => {
@@ -135,6 +275,203 @@ where
     }
 }
 
+/// A UART that receives and transmits through buffers
+///
+/// `service` moves bytes between the buffers and the UART. It has to be
+/// called from the UART interrupt handler after `enable_interrupts`, and the
+/// interrupt also has to be unmasked in the NVIC. When the `BufferedUart` is
+/// shared with the interrupt handler, e.g. through a `Mutex`, the other
+/// methods have to be called from within the same critical section.
+pub struct BufferedUart<'a, T> {
+    uart: Uart<T>,
+    rx: Queue<'a>,
+    tx: Queue<'a>,
+    tx_busy: bool,
+    error: Option<Error>,
+}
+
+impl<'a, T> BufferedUart<'a, T>
+where
+    T: Instance,
+{
+    /// Move received bytes into the receive buffer, and pending bytes from
+    /// the transmit buffer to the UART
+    pub fn service(&mut self) {
+        let uart = &self.uart.0;
+
+        if let Err(e) = take_error(uart) {
+            self.error = Some(e);
+        }
+
+        while uart.events_rxdrdy.read().bits() != 0 {
+            uart.events_rxdrdy.reset();
+            let byte = uart.rxd.read().bits() as u8;
+            if !self.rx.push(byte) {
+                self.error = Some(Error::BufferOverrun);
+            }
+        }
+
+        if uart.events_txdrdy.read().bits() != 0 {
+            uart.events_txdrdy.reset();
+            self.tx_busy = false;
+        }
+        if !self.tx_busy {
+            if let Some(byte) = self.tx.pop() {
+                uart.txd.write(|w| unsafe { w.bits(u32::from(byte)) });
+                self.tx_busy = true;
+            }
+        }
+    }
+
+    /// Read received bytes into `buf`, and return how many were read
+    ///
+    /// An error that occurred since the last call is returned first. The
+    /// bytes received before and after it can still be read afterwards.
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+
+        let mut n = 0;
+        for slot in buf.iter_mut() {
+            match self.rx.pop() {
+                Some(byte) => *slot = byte,
+                None => break,
+            }
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    /// Queue `data` for transmission, and return how many bytes fit into
+    /// the transmit buffer
+    pub fn write(&mut self, data: &[u8]) -> usize {
+        let n = data.iter().take_while(|&&byte| self.tx.push(byte)).count();
+
+        // Kick off the transmission, the interrupt takes care of the rest
+        self.service();
+
+        n
+    }
+
+    /// Have all queued bytes been sent?
+    pub fn is_flushed(&self) -> bool {
+        !self.tx_busy && self.tx.is_empty()
+    }
+
+    /// Enable the interrupts needed by `service`
+    ///
+    /// The interrupt also has to be unmasked in the NVIC, or the handler
+    /// won't get called.
+    pub fn enable_interrupts(&mut self) {
+        self.uart
+            .0
+            .intenset
+            .write(|w| w.rxdrdy().set().txdrdy().set().error().set());
+    }
+
+    /// Disable the interrupts needed by `service`
+    pub fn disable_interrupts(&mut self) {
+        self.uart
+            .0
+            .intenclr
+            .write(|w| w.rxdrdy().clear().txdrdy().clear().error().clear());
+    }
+
+    /// Wait for all queued bytes to be sent, and return the `Uart` and the
+    /// buffers
+    pub fn free(mut self) -> (Uart<T>, &'a mut [u8], &'a mut [u8]) {
+        self.disable_interrupts();
+        while !self.is_flushed() {
+            self.service();
+        }
+
+        // Leave `TXDRDY` set, as `Uart` expects it when idle
+        self.uart.0.events_txdrdy.write(|w| unsafe { w.bits(1) });
+
+        (self.uart, self.rx.buffer, self.tx.buffer)
+    }
+}
+
+impl<T> embedded_hal::serial::Read<u8> for BufferedUart<'_, T>
+where
+    T: Instance,
+{
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        let mut byte = [0];
+        match BufferedUart::read(self, &mut byte)? {
+            0 => Err(nb::Error::WouldBlock),
+            _ => Ok(byte[0]),
+        }
+    }
+}
+
+impl<T> embedded_hal::serial::Write<u8> for BufferedUart<'_, T>
+where
+    T: Instance,
+{
+    type Error = Void;
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), Self::Error> {
+        match BufferedUart::write(self, &[byte]) {
+            0 => Err(nb::Error::WouldBlock),
+            _ => Ok(()),
+        }
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        if self.is_flushed() {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+/// A FIFO of bytes in a caller-provided buffer
+struct Queue<'a> {
+    buffer: &'a mut [u8],
+    start: usize,
+    len: usize,
+}
+
+impl<'a> Queue<'a> {
+    fn new(buffer: &'a mut [u8]) -> Self {
+        Queue {
+            buffer,
+            start: 0,
+            len: 0,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Append `byte`, unless the queue is full
+    fn push(&mut self, byte: u8) -> bool {
+        if self.len == self.buffer.len() {
+            return false;
+        }
+        let end = (self.start + self.len) % self.buffer.len();
+        self.buffer[end] = byte;
+        self.len += 1;
+        true
+    }
+
+    fn pop(&mut self) -> Option<u8> {
+        if self.len == 0 {
+            return None;
+        }
+        let byte = self.buffer[self.start];
+        self.start = (self.start + 1) % self.buffer.len();
+        self.len -= 1;
+        Some(byte)
+    }
+}
+
 pub struct Pins {
     pub rxd: Pin<Input<Floating>>,
     pub txd: Pin<Output<PushPull>>,
@@ -4,26 +4,39 @@
 //!
 //! - nrf52832: Section 35
 //! - nrf52840: Section 6.34
+//!
+//! Continuous reception with `Uarte::into_ring_buffer` connects the UARTE to
+//! two timers through PPI. It is not available on the nRF9160, which has DPPI
+//! instead of PPI.
 use core::fmt;
+use core::marker::PhantomData;
 use core::ops::Deref;
 use core::sync::atomic::{compiler_fence, Ordering::SeqCst};
 
-use embedded_hal::digital::v2::OutputPin;
+use embedded_hal::{blocking, digital::v2::OutputPin, serial};
 
 #[cfg(feature = "52840")]
 use crate::target::UARTE1;
 
 #[cfg(feature = "9160")]
-use crate::target::{uarte0_ns as uarte0, UARTE0_NS as UARTE0, UARTE1_NS as UARTE1};
+use crate::target::{
+    uarte0_ns as uarte0, UARTE0_NS as UARTE0, UARTE0_S, UARTE1_NS as UARTE1, UARTE1_S, UARTE2_NS,
+    UARTE2_S, UARTE3_NS, UARTE3_S,
+};
 
 #[cfg(not(feature = "9160"))]
 use crate::target::{uarte0, UARTE0};
 
 use crate::gpio::{Floating, Input, Output, Pin, PushPull};
 use crate::prelude::*;
-use crate::slice_in_ram_or;
-use crate::target_constants::EASY_DMA_SIZE;
+use crate::rtc::{self, Rtc, RtcCompareReg, RtcInterrupt, Started};
+use crate::target_constants::{EASY_DMA_SIZE, FORCE_COPY_BUFFER_SIZE};
+use crate::time::{actual_baudrate, baudrate_register, Bps};
 use crate::timer::{self, Timer};
+use crate::{slice_in_ram, slice_in_ram_or};
+
+#[cfg(not(feature = "9160"))]
+use crate::target::PPI;
 
 // Re-export SVD variants to allow user to directly set values
 pub use uarte0::{baudrate::BAUDRATE_A as Baudrate, config::PARITY_A as Parity};
@@ -36,7 +49,7 @@ pub use uarte0::{baudrate::BAUDRATE_A as Baudrate, config::PARITY_A as Parity};
 ///   are disabled before using `Uarte`. See product specification:
 ///     - nrf52832: Section 15.2
 ///     - nrf52840: Section 6.1.2
-pub struct Uarte<T>(T);
+pub struct Uarte<T>(T, SerialTx, SerialRx);
 
 impl<T> Uarte<T>
 where
@@ -93,7 +106,55 @@ where
         // Configure frequency
         uarte.baudrate.write(|w| w.baudrate().variant(baudrate));
 
-        Uarte(uarte)
+        Uarte(uarte, SerialTx::new(), SerialRx::new())
+    }
+
+    /// Change the baud rate to one of the predefined values
+    ///
+    /// No transfer may be in progress. Flush the `embedded_hal::serial`
+    /// traits first if they have been used.
+    pub fn set_baudrate(&mut self, baudrate: Baudrate) {
+        self.0.baudrate.write(|w| w.baudrate().variant(baudrate));
+    }
+
+    /// Change the baud rate to an arbitrary value
+    ///
+    /// The predefined value of the product specification is used for the
+    /// standard rates. The register only has a limited resolution, so the
+    /// baud rate that is actually generated is returned. It can be compared
+    /// against `baud` to get the error.
+    ///
+    /// `baud` must be at most 1 Mbaud. The same restrictions as for
+    /// `set_baudrate` apply.
+    pub fn set_bps(&mut self, baud: Bps) -> Bps {
+        let bits = match standard_baudrate(baud) {
+            Some(baudrate) => baudrate.into(),
+            None => baudrate_register(baud),
+        };
+        self.0.baudrate.write(|w| unsafe { w.bits(bits) });
+        actual_baudrate(bits)
+    }
+
+    /// Change the parity
+    ///
+    /// The same restrictions as for `set_baudrate` apply.
+    pub fn set_parity(&mut self, parity: Parity) {
+        self.0.config.modify(|_, w| w.parity().variant(parity));
+    }
+
+    /// Enable or disable hardware flow control
+    ///
+    /// Flow control can only be enabled if both the `cts` and the `rts` pin
+    /// were passed to `new`. The same restrictions as for `set_baudrate`
+    /// apply.
+    pub fn set_hardware_flow_control(&mut self, enabled: bool) {
+        if enabled {
+            assert!(
+                self.0.psel.cts.read().connect().is_connected()
+                    && self.0.psel.rts.read().connect().is_connected()
+            );
+        }
+        self.0.config.modify(|_, w| w.hwfc().bit(enabled));
     }
 
     /// Write via UARTE
@@ -101,74 +162,38 @@ where
     /// This method uses transmits all bytes in `tx_buffer`
     ///
     /// The buffer must have a length of at most 255 bytes on the nRF52832
-    /// and at most 65535 bytes on the nRF52840.
+    /// and at most 65535 bytes on the nRF52840. A buffer that is not in RAM,
+    /// e.g. a string literal, is copied to the stack and sent in chunks of
+    /// `FORCE_COPY_BUFFER_SIZE` bytes instead.
+    ///
+    /// Bytes buffered by `serial::Write` are sent first. The same applies to
+    /// `start_write` and `fmt::Write`.
     pub fn write(&mut self, tx_buffer: &[u8]) -> Result<(), Error> {
-        if tx_buffer.len() > EASY_DMA_SIZE {
-            return Err(Error::TxBufferTooLong);
-        }
-
-        // We can only DMA out of RAM
-        slice_in_ram_or(tx_buffer, Error::BufferNotInRAM)?;
-
-        // Conservative compiler fence to prevent optimizations that do not
-        // take in to account actions by DMA. The fence has been placed here,
-        // before any DMA action has started
-        compiler_fence(SeqCst);
+        serial_flush(&self.0, &mut self.1)?;
+        write_tx(&self.0, tx_buffer)
+    }
 
-        // Reset the events.
-        self.0.events_endtx.reset();
-        self.0.events_txstopped.reset();
-
-        // Set up the DMA write
-        self.0.txd.ptr.write(|w|
-            // We're giving the register a pointer to the stack. Since we're
-            // waiting for the UARTE transaction to end before this stack pointer
-            // becomes invalid, there's nothing wrong here.
-            //
-            // The PTR field is a full 32 bits wide and accepts the full range
-            // of values.
-            unsafe { w.ptr().bits(tx_buffer.as_ptr() as u32) });
-        self.0.txd.maxcnt.write(|w|
-            // We're giving it the length of the buffer, so no danger of
-            // accessing invalid memory. We have verified that the length of the
-            // buffer fits in an `u8`, so the cast to `u8` is also fine.
-            //
-            // The MAXCNT field is 8 bits wide and accepts the full range of
-            // values.
-            unsafe { w.maxcnt().bits(tx_buffer.len() as _) });
-
-        // Start UARTE Transmit transaction
-        self.0.tasks_starttx.write(|w|
-            // `1` is a valid value to write to task registers.
-            unsafe { w.bits(1) });
-
-        // Wait for transmission to end
-        let mut endtx;
-        let mut txstopped;
-        loop {
-            endtx = self.0.events_endtx.read().bits() != 0;
-            txstopped = self.0.events_txstopped.read().bits() != 0;
-            if endtx || txstopped {
-                break;
-            }
+    /// Start writing `tx_buffer` without waiting for the transmission to end
+    ///
+    /// The returned `Transfer` owns the `Uarte` and the buffer until the
+    /// transmission has ended. On error, the `Uarte` is handed back.
+    ///
+    /// The buffer has the same length limits as for `write`, and has to be
+    /// located in RAM.
+    pub fn start_write(
+        mut self,
+        tx_buffer: &'static [u8],
+    ) -> Result<Transfer<T, &'static [u8]>, (Error, Self)> {
+        if let Err(e) = serial_flush(&self.0, &mut self.1) {
+            return Err((e, self));
         }
-
-        // Conservative compiler fence to prevent optimizations that do not
-        // take in to account actions by DMA. The fence has been placed here,
-        // after all possible DMA actions have completed
-        compiler_fence(SeqCst);
-
-        if txstopped {
-            return Err(Error::Transmit);
+        match start_tx(&self.0, tx_buffer) {
+            Ok(()) => Ok(Transfer {
+                uarte: self,
+                buffer: tx_buffer,
+            }),
+            Err(e) => Err((e, self)),
         }
-
-        // Lower power consumption by disabling the transmitter once we're
-        // finished
-        self.0.tasks_stoptx.write(|w|
-            // `1` is a valid value to write to task registers.
-            unsafe { w.bits(1) });
-
-        Ok(())
     }
 
     /// Read via UARTE
@@ -176,20 +201,21 @@ where
     /// This method fills all bytes in `rx_buffer`, and blocks
     /// until the buffer is full.
     ///
+    /// Errors on the line are reported as `Overrun`, `Parity`, `Framing` or
+    /// `Break`, after the buffer has been filled. A BREAK condition is
+    /// received as a `0` byte, so a LIN-style break can be awaited by reading
+    /// single bytes until `Error::Break` is returned.
+    ///
+    /// If `serial::Read::read` is waiting for a byte, that reception is
+    /// stopped first. A byte it has already received ends up in
+    /// `rx_buffer[0]`, followed by the bytes received afterwards. The same
+    /// applies to all other blocking reads, while `start_read` returns
+    /// `Error::PendingByte` in this case.
+    ///
     /// The buffer must have a length of at most 255 bytes
     pub fn read(&mut self, rx_buffer: &mut [u8]) -> Result<(), Error> {
-        self.start_read(rx_buffer)?;
-
-        // Wait for transmission to end
-        while self.0.events_endrx.read().bits() == 0 {}
-
-        self.finalize_read();
-
-        if self.0.rxd.amount.read().bits() != rx_buffer.len() as u32 {
-            return Err(Error::Receive);
-        }
-
-        Ok(())
+        let uarte = &self.0;
+        read_after_serial(uarte, &mut self.2, rx_buffer, |buf| read_rx(uarte, buf))
     }
 
     /// Read via UARTE
@@ -215,162 +241,1744 @@ where
     where
         I: timer::Instance,
     {
-        // Start the read
-        self.start_read(rx_buffer)?;
+        let uarte = &self.0;
+        read_after_serial(uarte, &mut self.2, rx_buffer, |buf| {
+            read_rx_timeout(uarte, buf, timer, cycles)
+        })
+    }
 
-        // Start the timeout timer
-        timer.start(cycles);
+    /// Read via UARTE, with a timeout measured by an RTC
+    ///
+    /// This works like `read_timeout`, but uses the compare register `reg`
+    /// of a running RTC, so no high frequency timer has to be kept running.
+    /// The timeout expires after `ticks` RTC ticks, which must be at least
+    /// 2 and less than 2^24.
+    ///
+    /// The compare event is enabled while waiting, and disabled again
+    /// afterwards.
+    pub fn read_timeout_rtc<R>(
+        &mut self,
+        rx_buffer: &mut [u8],
+        rtc: &mut Rtc<R, Started>,
+        reg: RtcCompareReg,
+        ticks: u32,
+    ) -> Result<(), Error>
+    where
+        R: rtc::Instance,
+    {
+        let uarte = &self.0;
+        read_after_serial(uarte, &mut self.2, rx_buffer, |buf| {
+            read_rx_timeout_rtc(uarte, buf, rtc, reg, ticks)
+        })
+    }
 
-        // Wait for transmission to end
-        let mut event_complete = false;
-        let mut timeout_occured = false;
+    /// Read via UARTE until `delimiter` is received or the line is idle
+    ///
+    /// Bytes are stored in `rx_buffer` until the `delimiter` has been
+    /// received, the buffer is full, or no byte has been received for
+    /// `idle_ticks` ticks of the RTC. The number of bytes received is
+    /// returned, including the delimiter. It is `0` if nothing was received
+    /// within the first idle period.
+    ///
+    /// The RTC is used like for `read_timeout_rtc`.
+    pub fn read_until<R>(
+        &mut self,
+        rx_buffer: &mut [u8],
+        delimiter: u8,
+        rtc: &mut Rtc<R, Started>,
+        reg: RtcCompareReg,
+        idle_ticks: u32,
+    ) -> Result<usize, Error>
+    where
+        R: rtc::Instance,
+    {
+        read_until_after_serial(
+            &self.0,
+            &mut self.2,
+            rx_buffer,
+            delimiter,
+            rtc,
+            reg,
+            idle_ticks,
+        )
+    }
 
-        loop {
-            event_complete |= self.0.events_endrx.read().bits() != 0;
-            timeout_occured |= timer.wait().is_ok();
-            if event_complete || timeout_occured {
-                break;
-            }
+    /// Start reading into `rx_buffer` without waiting for it to be filled
+    ///
+    /// The returned `Transfer` owns the `Uarte` and the buffer until the
+    /// reception has ended. On error, the `Uarte` is handed back.
+    ///
+    /// If `serial::Read::read` has already received a byte, this fails with
+    /// `Error::PendingByte` until the byte has been read with it.
+    ///
+    /// The buffer must have a length of at most 255 bytes
+    pub fn start_read(
+        mut self,
+        rx_buffer: &'static mut [u8],
+    ) -> Result<Transfer<T, &'static mut [u8]>, (Error, Self)> {
+        stop_serial_rx(&self.0, &mut self.2);
+        if self.2.pending.is_some() {
+            return Err((Error::PendingByte, self));
         }
-
-        if !event_complete {
-            // Cancel the reception if it did not complete until now
-            self.cancel_read();
+        match start_rx(&self.0, rx_buffer) {
+            Ok(()) => Ok(Transfer {
+                uarte: self,
+                buffer: rx_buffer,
+            }),
+            Err(e) => Err((e, self)),
         }
+    }
+
+    /// Split into a transmitting and a receiving half
+    ///
+    /// The halves can be used independently, e.g. from different tasks or
+    /// interrupt priorities. Each of them only uses its own EasyDMA channel
+    /// and events. `Uarte::join` puts them back together.
+    pub fn split(self) -> (UarteTx<T>, UarteRx<T>) {
+        (
+            UarteTx {
+                uarte: self.0,
+                serial: self.1,
+            },
+            UarteRx {
+                serial: self.2,
+                _marker: PhantomData,
+            },
+        )
+    }
+
+    /// Recombine the halves created by `split`
+    pub fn join(tx: UarteTx<T>, rx: UarteRx<T>) -> Self {
+        Uarte(tx.uarte, tx.serial, rx.serial)
+    }
+
+    /// Enable the interrupt for an event
+    ///
+    /// The interrupt also has to be unmasked in the NVIC, or the handler
+    /// won't get called.
+    pub fn enable_interrupt(&mut self, evt: UarteEvent) {
+        self.0.intenset.write(|w| unsafe { w.bits(evt.mask()) });
+    }
+
+    /// Disable the interrupt for an event
+    pub fn disable_interrupt(&mut self, evt: UarteEvent) {
+        self.0.intenclr.write(|w| unsafe { w.bits(evt.mask()) });
+    }
+
+    /// Obtain the state of an event, and optionally clear the event if it is
+    /// set
+    pub fn get_event_triggered(&mut self, evt: UarteEvent, clear_on_read: bool) -> bool {
+        event_triggered(&self.0, evt, clear_on_read)
+    }
+
+    /// Return and clear the error that occurred during the last reception
+    ///
+    /// This is useful after a reception started with `start_read`, or from
+    /// the handler of the `UarteEvent::Error` interrupt, e.g. to detect a
+    /// BREAK condition. `read` and `read_timeout` do this themselves.
+    pub fn take_error(&mut self) -> Result<(), Error> {
+        take_rx_error(&self.0)
+    }
+
+    /// Set the buffer used by `embedded_hal::serial::Read`
+    ///
+    /// EasyDMA writes into it while `serial::Read::read` waits for a byte,
+    /// and keeps doing so while the `Uarte` is moved, so it has to be
+    /// `'static`.
+    pub fn set_serial_rx_buffer(&mut self, buffer: &'static mut [u8; 1]) {
+        stop_serial_rx(&self.0, &mut self.2);
+        self.2.buffer = Some(buffer);
+    }
 
-        // Cleanup, even in the error case
-        self.finalize_read();
+    /// Return the raw interface to the underlying UARTE peripheral
+    ///
+    /// Bytes buffered by `serial::Write` are sent first.
+    pub fn free(mut self) -> T {
+        // There is no way to report an error here, and the bytes couldn't be
+        // sent later on either
+        let _ = serial_flush(&self.0, &mut self.1);
+        stop_serial_rx(&self.0, &mut self.2);
+        self.0
+    }
+}
 
-        let bytes_read = self.0.rxd.amount.read().bits() as usize;
+/// The predefined `BAUDRATE` value for `baud`, if it is a standard rate
+fn standard_baudrate(baud: Bps) -> Option<Baudrate> {
+    Some(match baud.0 {
+        1200 => Baudrate::BAUD1200,
+        2400 => Baudrate::BAUD2400,
+        4800 => Baudrate::BAUD4800,
+        9600 => Baudrate::BAUD9600,
+        14400 => Baudrate::BAUD14400,
+        19200 => Baudrate::BAUD19200,
+        28800 => Baudrate::BAUD28800,
+        31250 => Baudrate::BAUD31250,
+        38400 => Baudrate::BAUD38400,
+        56000 => Baudrate::BAUD56000,
+        57600 => Baudrate::BAUD57600,
+        76800 => Baudrate::BAUD76800,
+        115200 => Baudrate::BAUD115200,
+        230400 => Baudrate::BAUD230400,
+        250000 => Baudrate::BAUD250000,
+        460800 => Baudrate::BAUD460800,
+        921600 => Baudrate::BAUD921600,
+        1_000_000 => Baudrate::BAUD1M,
+        _ => return None,
+    })
+}
 
-        if timeout_occured && !event_complete {
-            return Err(Error::Timeout(bytes_read));
+/// Transmit all bytes in `tx_buffer` and block until they have been sent
+fn write_tx(uarte: &uarte0::RegisterBlock, tx_buffer: &[u8]) -> Result<(), Error> {
+    if slice_in_ram(tx_buffer) {
+        write_tx_dma(uarte, tx_buffer)
+    } else {
+        // EasyDMA can only read from RAM, so copy the data in chunks
+        for chunk in tx_buffer.chunks(FORCE_COPY_BUFFER_SIZE) {
+            write_tx_copy(uarte, chunk)?;
         }
+        Ok(())
+    }
+}
+
+/// Copy `chunk` to the stack and transmit it from there
+fn write_tx_copy(uarte: &uarte0::RegisterBlock, chunk: &[u8]) -> Result<(), Error> {
+    let mut buf = [0u8; FORCE_COPY_BUFFER_SIZE];
+    buf[..chunk.len()].copy_from_slice(chunk);
+
+    write_tx_dma(uarte, &buf[..chunk.len()])
+}
+
+/// Transmit `tx_buffer`, which must be in RAM, and block until it has been
+/// sent
+fn write_tx_dma(uarte: &uarte0::RegisterBlock, tx_buffer: &[u8]) -> Result<(), Error> {
+    start_tx(uarte, tx_buffer)?;
 
-        if bytes_read != rx_buffer.len() as usize {
-            return Err(Error::Receive);
+    // Wait for transmission to end
+    let mut endtx;
+    let mut txstopped;
+    loop {
+        endtx = uarte.events_endtx.read().bits() != 0;
+        txstopped = uarte.events_txstopped.read().bits() != 0;
+        if endtx || txstopped {
+            break;
         }
+    }
 
-        Ok(())
+    // Conservative compiler fence to prevent optimizations that do not
+    // take in to account actions by DMA. The fence has been placed here,
+    // after all possible DMA actions have completed
+    compiler_fence(SeqCst);
+
+    if txstopped {
+        return Err(Error::Transmit);
+    }
+
+    // Lower power consumption by disabling the transmitter once we're
+    // finished
+    uarte.tasks_stoptx.write(|w|
+        // `1` is a valid value to write to task registers.
+        unsafe { w.bits(1) });
+
+    Ok(())
+}
+
+/// Start a UARTE write transaction by setting the control
+/// values and triggering a write task
+fn start_tx(uarte: &uarte0::RegisterBlock, tx_buffer: &[u8]) -> Result<(), Error> {
+    if tx_buffer.len() > EASY_DMA_SIZE {
+        return Err(Error::TxBufferTooLong);
+    }
+
+    // We can only DMA out of RAM
+    slice_in_ram_or(tx_buffer, Error::BufferNotInRAM)?;
+
+    // Conservative compiler fence to prevent optimizations that do not
+    // take in to account actions by DMA. The fence has been placed here,
+    // before any DMA action has started
+    compiler_fence(SeqCst);
+
+    // Reset the events.
+    uarte.events_endtx.reset();
+    uarte.events_txstopped.reset();
+
+    // Set up the DMA write
+    uarte.txd.ptr.write(|w|
+        // We're giving the register a pointer to the buffer. The caller
+        // makes sure that the buffer outlives the transaction, either by
+        // waiting for it to end or by requiring a `'static` buffer.
+        //
+        // The PTR field is a full 32 bits wide and accepts the full range
+        // of values.
+        unsafe { w.ptr().bits(tx_buffer.as_ptr() as u32) });
+    uarte.txd.maxcnt.write(|w|
+        // We're giving it the length of the buffer, so no danger of
+        // accessing invalid memory. We have verified that the length of the
+        // buffer fits in an `u8`, so the cast to `u8` is also fine.
+        //
+        // The MAXCNT field is 8 bits wide and accepts the full range of
+        // values.
+        unsafe { w.maxcnt().bits(tx_buffer.len() as _) });
+
+    // Start UARTE Transmit transaction
+    uarte.tasks_starttx.write(|w|
+        // `1` is a valid value to write to task registers.
+        unsafe { w.bits(1) });
+
+    Ok(())
+}
+
+/// Fill all bytes in `rx_buffer` and block until the buffer is full
+fn read_rx(uarte: &uarte0::RegisterBlock, rx_buffer: &mut [u8]) -> Result<(), Error> {
+    start_rx(uarte, rx_buffer)?;
+
+    // Wait for transmission to end
+    while uarte.events_endrx.read().bits() == 0 {}
+
+    finalize_rx(uarte);
+    take_rx_error(uarte)?;
+
+    if uarte.rxd.amount.read().bits() != rx_buffer.len() as u32 {
+        return Err(Error::Receive);
+    }
+
+    Ok(())
+}
+
+/// Fill all bytes in `rx_buffer` and block until the buffer is full or the
+/// timeout expires
+fn read_rx_timeout<I>(
+    uarte: &uarte0::RegisterBlock,
+    rx_buffer: &mut [u8],
+    timer: &mut Timer<I>,
+    cycles: u32,
+) -> Result<(), Error>
+where
+    I: timer::Instance,
+{
+    // Start the timeout timer
+    timer.start(cycles);
+
+    read_rx_until_expired(uarte, rx_buffer, || timer.wait().is_ok())
+}
+
+/// Fill all bytes in `rx_buffer` and block until the buffer is full or the
+/// RTC timeout expires
+fn read_rx_timeout_rtc<R>(
+    uarte: &uarte0::RegisterBlock,
+    rx_buffer: &mut [u8],
+    rtc: &mut Rtc<R, Started>,
+    reg: RtcCompareReg,
+    ticks: u32,
+) -> Result<(), Error>
+where
+    R: rtc::Instance,
+{
+    let evt = start_rtc_timeout(rtc, reg, ticks);
+    let result = read_rx_until_expired(uarte, rx_buffer, || rtc.get_event_triggered(evt, true));
+    rtc.disable_event(evt);
+
+    result
+}
+
+/// Receive single bytes until `delimiter`, a full buffer or an idle period
+fn read_rx_until<R>(
+    uarte: &uarte0::RegisterBlock,
+    rx_buffer: &mut [u8],
+    delimiter: u8,
+    rtc: &mut Rtc<R, Started>,
+    reg: RtcCompareReg,
+    idle_ticks: u32,
+) -> Result<usize, Error>
+where
+    R: rtc::Instance,
+{
+    let mut n = 0;
+    let mut result = Ok(());
+
+    while n < rx_buffer.len() {
+        // Restart the idle period for every byte
+        let evt = start_rtc_timeout(rtc, reg, idle_ticks);
+        result = read_rx_until_expired(uarte, &mut rx_buffer[n..n + 1], || {
+            rtc.get_event_triggered(evt, true)
+        });
+
+        match result {
+            Ok(()) => n += 1,
+            Err(Error::Timeout(received)) => {
+                n += received;
+                result = Ok(());
+                break;
+            }
+            Err(_) => break,
+        }
+        if rx_buffer[n - 1] == delimiter {
+            break;
+        }
     }
 
-    /// Start a UARTE read transaction by setting the control
-    /// values and triggering a read task
-    fn start_read(&mut self, rx_buffer: &mut [u8]) -> Result<(), Error> {
-        // This is overly restrictive. See (similar SPIM issue):
-        // https://github.com/nrf-rs/nrf52/issues/17
-        if rx_buffer.len() > u8::max_value() as usize {
-            return Err(Error::TxBufferTooLong);
+    rtc.disable_event(RtcInterrupt::from(reg));
+    result.map(|()| n)
+}
+
+/// Make the compare register `reg` expire after `ticks`, and return its event
+fn start_rtc_timeout<R>(rtc: &mut Rtc<R, Started>, reg: RtcCompareReg, ticks: u32) -> RtcInterrupt
+where
+    R: rtc::Instance,
+{
+    assert!((2..(1 << 24)).contains(&ticks));
+
+    let evt = RtcInterrupt::from(reg);
+
+    // The event is only generated while it is enabled
+    rtc.enable_event(evt);
+    rtc.get_event_triggered(evt, true);
+
+    let compare = (rtc.get_counter() + ticks) & 0xFF_FFFF;
+    rtc.set_compare(reg, compare).unwrap();
+
+    evt
+}
+
+/// Fill all bytes in `rx_buffer` and block until the buffer is full or
+/// `expired` returns `true`
+fn read_rx_until_expired<F>(
+    uarte: &uarte0::RegisterBlock,
+    rx_buffer: &mut [u8],
+    mut expired: F,
+) -> Result<(), Error>
+where
+    F: FnMut() -> bool,
+{
+    // Start the read
+    start_rx(uarte, rx_buffer)?;
+
+    // Wait for transmission to end
+    let mut event_complete = false;
+    let mut timeout_occured = false;
+
+    loop {
+        event_complete |= uarte.events_endrx.read().bits() != 0;
+        timeout_occured |= expired();
+        if event_complete || timeout_occured {
+            break;
         }
+    }
 
-        // NOTE: RAM slice check is not necessary, as a mutable slice can only be
-        // built from data located in RAM
+    if !event_complete {
+        // Cancel the reception if it did not complete until now
+        cancel_rx(uarte);
+    }
 
-        // Conservative compiler fence to prevent optimizations that do not
-        // take in to account actions by DMA. The fence has been placed here,
-        // before any DMA action has started
-        compiler_fence(SeqCst);
+    // Cleanup, even in the error case
+    finalize_rx(uarte);
+    take_rx_error(uarte)?;
 
-        // Set up the DMA read
-        self.0.rxd.ptr.write(|w|
-            // We're giving the register a pointer to the stack. Since we're
-            // waiting for the UARTE transaction to end before this stack pointer
-            // becomes invalid, there's nothing wrong here.
-            //
-            // The PTR field is a full 32 bits wide and accepts the full range
-            // of values.
-            unsafe { w.ptr().bits(rx_buffer.as_ptr() as u32) });
-        self.0.rxd.maxcnt.write(|w|
-            // We're giving it the length of the buffer, so no danger of
-            // accessing invalid memory. We have verified that the length of the
-            // buffer fits in an `u8`, so the cast to `u8` is also fine.
-            //
-            // The MAXCNT field is at least 8 bits wide and accepts the full
-            // range of values.
-            unsafe { w.maxcnt().bits(rx_buffer.len() as _) });
-
-        // Start UARTE Receive transaction
-        self.0.tasks_startrx.write(|w|
-            // `1` is a valid value to write to task registers.
-            unsafe { w.bits(1) });
+    let bytes_read = uarte.rxd.amount.read().bits() as usize;
 
-        Ok(())
+    if timeout_occured && !event_complete {
+        return Err(Error::Timeout(bytes_read));
     }
 
-    /// Finalize a UARTE read transaction by clearing the event
-    fn finalize_read(&mut self) {
-        // Reset the event, otherwise it will always read `1` from now on.
-        self.0.events_endrx.write(|w| w);
+    if bytes_read != rx_buffer.len() as usize {
+        return Err(Error::Receive);
+    }
 
-        // Conservative compiler fence to prevent optimizations that do not
-        // take in to account actions by DMA. The fence has been placed here,
-        // after all possible DMA actions have completed
-        compiler_fence(SeqCst);
+    Ok(())
+}
+
+/// Start a UARTE read transaction by setting the control
+/// values and triggering a read task
+fn start_rx(uarte: &uarte0::RegisterBlock, rx_buffer: &mut [u8]) -> Result<(), Error> {
+    // This is overly restrictive. See (similar SPIM issue):
+    // https://github.com/nrf-rs/nrf52/issues/17
+    if rx_buffer.len() > u8::max_value() as usize {
+        return Err(Error::TxBufferTooLong);
     }
 
-    /// Stop an unfinished UART read transaction and flush FIFO to DMA buffer
-    fn cancel_read(&mut self) {
-        // Stop reception
-        self.0.tasks_stoprx.write(|w| unsafe { w.bits(1) });
+    // NOTE: RAM slice check is not necessary, as a mutable slice can only be
+    // built from data located in RAM
+
+    // Forget about errors of earlier receptions
+    uarte.events_error.reset();
+    clear_errorsrc(uarte);
+
+    // Conservative compiler fence to prevent optimizations that do not
+    // take in to account actions by DMA. The fence has been placed here,
+    // before any DMA action has started
+    compiler_fence(SeqCst);
+
+    // Set up the DMA read
+    uarte.rxd.ptr.write(|w|
+        // We're giving the register a pointer to the buffer. The caller
+        // makes sure that the buffer outlives the transaction, either by
+        // waiting for it to end or by requiring a `'static` buffer.
+        //
+        // The PTR field is a full 32 bits wide and accepts the full range
+        // of values.
+        unsafe { w.ptr().bits(rx_buffer.as_ptr() as u32) });
+    uarte.rxd.maxcnt.write(|w|
+        // We're giving it the length of the buffer, so no danger of
+        // accessing invalid memory. We have verified that the length of the
+        // buffer fits in an `u8`, so the cast to `u8` is also fine.
+        //
+        // The MAXCNT field is at least 8 bits wide and accepts the full
+        // range of values.
+        unsafe { w.maxcnt().bits(rx_buffer.len() as _) });
 
-        // Wait for the reception to have stopped
-        while self.0.events_rxto.read().bits() == 0 {}
+    // Start UARTE Receive transaction
+    uarte.tasks_startrx.write(|w|
+        // `1` is a valid value to write to task registers.
+        unsafe { w.bits(1) });
 
-        // Reset the event flag
-        self.0.events_rxto.write(|w| w);
+    Ok(())
+}
 
-        // Ask UART to flush FIFO to DMA buffer
-        self.0.tasks_flushrx.write(|w| unsafe { w.bits(1) });
+/// Finalize a UARTE read transaction by clearing the event
+fn finalize_rx(uarte: &uarte0::RegisterBlock) {
+    // Reset the event, otherwise it will always read `1` from now on.
+    uarte.events_endrx.write(|w| w);
 
-        // Wait for the flush to complete.
-        while self.0.events_endrx.read().bits() == 0 {}
+    // Conservative compiler fence to prevent optimizations that do not
+    // take in to account actions by DMA. The fence has been placed here,
+    // after all possible DMA actions have completed
+    compiler_fence(SeqCst);
+}
 
-        // The event flag itself is later reset by `finalize_read`.
+/// Return and clear the error that occurred during reception, if any
+///
+/// If several errors occurred, a break is reported before a framing error,
+/// and both before parity and overrun errors.
+fn take_rx_error(uarte: &uarte0::RegisterBlock) -> Result<(), Error> {
+    if uarte.events_error.read().bits() == 0 {
+        return Ok(());
     }
+    uarte.events_error.reset();
 
-    /// Return the raw interface to the underlying UARTE peripheral
-    pub fn free(self) -> T {
-        self.0
+    let src = clear_errorsrc(uarte);
+    if src & ERRORSRC_BREAK != 0 {
+        Err(Error::Break)
+    } else if src & ERRORSRC_FRAMING != 0 {
+        Err(Error::Framing)
+    } else if src & ERRORSRC_PARITY != 0 {
+        Err(Error::Parity)
+    } else if src & ERRORSRC_OVERRUN != 0 {
+        Err(Error::Overrun)
+    } else {
+        Err(Error::Receive)
     }
 }
 
-impl<T> fmt::Write for Uarte<T>
+/// Clear the `ERRORSRC` register and return the flags that were set
+fn clear_errorsrc(uarte: &uarte0::RegisterBlock) -> u32 {
+    // The flags are cleared by writing `1` to them
+    let src = uarte.errorsrc.read().bits();
+    uarte.errorsrc.write(|w| unsafe { w.bits(src) });
+    src
+}
+
+/// Flags in `ERRORSRC`
+const ERRORSRC_OVERRUN: u32 = 1 << 0;
+const ERRORSRC_PARITY: u32 = 1 << 1;
+const ERRORSRC_FRAMING: u32 = 1 << 2;
+const ERRORSRC_BREAK: u32 = 1 << 3;
+
+/// Stop an unfinished UART read transaction and flush FIFO to DMA buffer
+fn cancel_rx(uarte: &uarte0::RegisterBlock) {
+    // Stop reception
+    uarte.tasks_stoprx.write(|w| unsafe { w.bits(1) });
+
+    // Wait for the reception to have stopped
+    while uarte.events_rxto.read().bits() == 0 {}
+
+    // Reset the event flag
+    uarte.events_rxto.write(|w| w);
+
+    // Ask UART to flush FIFO to DMA buffer
+    uarte.tasks_flushrx.write(|w| unsafe { w.bits(1) });
+
+    // Wait for the flush to complete.
+    while uarte.events_endrx.read().bits() == 0 {}
+
+    // The event flag itself is later reset by `finalize_rx`.
+}
+
+/// Stop a reception into `buffer` that was started by `start_rx`, and return
+/// the number of bytes that have been received
+///
+/// Bytes that are still held in the receive FIFO are flushed into the rest of
+/// the buffer. If the buffer is full already, they are left in the FIFO, and
+/// end up in the next reception.
+fn stop_rx(uarte: &uarte0::RegisterBlock, buffer: &mut [u8]) -> usize {
+    uarte.tasks_stoprx.write(|w| unsafe { w.bits(1) });
+
+    // Once the receiver has stopped, `ENDRX` has been generated, even if the
+    // buffer isn't full, and `RXD.AMOUNT` holds the number of bytes in it
+    while uarte.events_rxto.read().bits() == 0 {}
+    uarte.events_rxto.reset();
+    while uarte.events_endrx.read().bits() == 0 {}
+    uarte.events_endrx.reset();
+
+    let mut received = uarte.rxd.amount.read().bits() as usize;
+
+    if received < buffer.len() {
+        // Flush the FIFO behind the bytes that have already been received,
+        // instead of over them
+        let rest = &mut buffer[received..];
+        uarte
+            .rxd
+            .ptr
+            .write(|w| unsafe { w.ptr().bits(rest.as_mut_ptr() as u32) });
+        uarte
+            .rxd
+            .maxcnt
+            .write(|w| unsafe { w.maxcnt().bits(rest.len() as _) });
+
+        // `ENDRX` is generated even if the FIFO is empty
+        uarte.tasks_flushrx.write(|w| unsafe { w.bits(1) });
+        while uarte.events_endrx.read().bits() == 0 {}
+
+        received += uarte.rxd.amount.read().bits() as usize;
+    }
+
+    finalize_rx(uarte);
+
+    received
+}
+
+/// Obtain the state of an event, and optionally clear the event if it is set
+fn event_triggered(uarte: &uarte0::RegisterBlock, evt: UarteEvent, clear_on_read: bool) -> bool {
+    match evt {
+        UarteEvent::EndRx => event_triggered!(uarte, events_endrx, clear_on_read),
+        UarteEvent::EndTx => event_triggered!(uarte, events_endtx, clear_on_read),
+        UarteEvent::Error => event_triggered!(uarte, events_error, clear_on_read),
+    }
+}
+
+/// A UARTE transfer that runs in the background
+///
+/// Created by `Uarte::start_write` and `Uarte::start_read`. The `ENDTX` and
+/// `ENDRX` interrupts signal the end of a transfer, after which `wait`
+/// returns immediately.
+pub struct Transfer<T, B> {
+    uarte: Uarte<T>,
+    buffer: B,
+}
+
+impl<T> Transfer<T, &'static [u8]>
 where
     T: Instance,
 {
-    fn write_str(&mut self, s: &str) -> fmt::Result {
-        // Copy all data into an on-stack buffer so we never try to EasyDMA from
-        // flash
-        let buf = &mut [0; 16][..];
-        for block in s.as_bytes().chunks(16) {
-            buf[..block.len()].copy_from_slice(block);
-            self.write(&buf[..block.len()]).map_err(|_| fmt::Error)?;
-        }
+    /// Has the transmission ended?
+    pub fn is_done(&self) -> bool {
+        self.uarte.0.events_endtx.read().bits() != 0
+    }
 
-        Ok(())
+    /// Block until the transmission has ended, and return the `Uarte` and
+    /// the buffer
+    ///
+    /// This clears the `ENDTX` event.
+    pub fn wait(self) -> (Uarte<T>, &'static [u8]) {
+        end_tx(&self.uarte.0);
+        (self.uarte, self.buffer)
     }
 }
 
-pub struct Pins {
-    pub rxd: Pin<Input<Floating>>,
-    pub txd: Pin<Output<PushPull>>,
-    pub cts: Option<Pin<Input<Floating>>>,
-    pub rts: Option<Pin<Output<PushPull>>>,
+/// Block until `ENDTX`, then clear it and stop the transmitter
+fn end_tx(uarte: &uarte0::RegisterBlock) {
+    while uarte.events_endtx.read().bits() == 0 {}
+
+    // Conservative compiler fence to prevent optimizations that do not
+    // take in to account actions by DMA. The fence has been placed here,
+    // after all possible DMA actions have completed
+    compiler_fence(SeqCst);
+
+    uarte.events_endtx.reset();
+
+    // Lower power consumption by disabling the transmitter once we're
+    // finished
+    uarte.tasks_stoptx.write(|w| unsafe { w.bits(1) });
 }
 
-#[derive(Debug)]
-pub enum Error {
-    TxBufferTooLong,
-    RxBufferTooLong,
-    Transmit,
-    Receive,
-    Timeout(usize),
-    BufferNotInRAM,
+impl<T> Transfer<T, &'static mut [u8]>
+where
+    T: Instance,
+{
+    /// Has the buffer been filled?
+    pub fn is_done(&self) -> bool {
+        self.uarte.0.events_endrx.read().bits() != 0
+    }
+
+    /// Block until the buffer has been filled, and return the `Uarte` and the
+    /// buffer
+    ///
+    /// This clears the `ENDRX` event. Errors during the reception can be
+    /// checked afterwards with `Uarte::take_error`.
+    pub fn wait(self) -> (Uarte<T>, &'static mut [u8]) {
+        let uarte = self.uarte;
+        while uarte.0.events_endrx.read().bits() == 0 {}
+
+        finalize_rx(&uarte.0);
+
+        (uarte, self.buffer)
+    }
+
+    /// Stop the reception, and return the `Uarte`, the buffer and the number
+    /// of bytes that have been received
+    ///
+    /// The received bytes are at the start of the buffer. Errors during the
+    /// reception can be checked afterwards with `Uarte::take_error`.
+    pub fn cancel(self) -> (Uarte<T>, &'static mut [u8], usize) {
+        let Transfer { uarte, buffer } = self;
+        let received = stop_rx(&uarte.0, buffer);
+
+        (uarte, buffer, received)
+    }
+}
+
+impl<T> fmt::Write for Uarte<T>
+where
+    T: Instance,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        write_str(&self.0, &mut self.1, s)
+    }
 }
 
-pub trait Instance: Deref<Target = uarte0::RegisterBlock> {}
+fn write_str(uarte: &uarte0::RegisterBlock, tx: &mut SerialTx, s: &str) -> fmt::Result {
+    serial_flush(uarte, tx).map_err(|_| fmt::Error)?;
 
-impl Instance for UARTE0 {}
+    // Data that is not in RAM is copied by `write_tx`
+    for block in s.as_bytes().chunks(EASY_DMA_SIZE) {
+        write_tx(uarte, block).map_err(|_| fmt::Error)?;
+    }
 
-#[cfg(any(feature = "52840", feature = "9160"))]
-impl Instance for UARTE1 {}
+    Ok(())
+}
+
+/// Size of the transmit buffer of the `embedded_hal::serial::Write`
+/// implementation
+pub const SERIAL_TX_BUFFER_SIZE: usize = 16;
+
+/// Transmit state of the `embedded_hal::serial` implementation
+///
+/// The buffer is only used by EasyDMA while a method of the owning `Uarte` or
+/// `UarteTx` is blocking, so it can live inside of them.
+struct SerialTx {
+    buffer: [u8; SERIAL_TX_BUFFER_SIZE],
+    len: usize,
+}
+
+impl SerialTx {
+    fn new() -> Self {
+        SerialTx {
+            buffer: [0; SERIAL_TX_BUFFER_SIZE],
+            len: 0,
+        }
+    }
+}
+
+/// Receive state of the `embedded_hal::serial` implementation
+///
+/// EasyDMA keeps writing into the buffer between calls, so it has to be
+/// supplied by the caller and can't move with the `Uarte` or `UarteRx`.
+struct SerialRx {
+    buffer: Option<&'static mut [u8; 1]>,
+    busy: bool,
+    pending: Option<u8>,
+}
+
+impl SerialRx {
+    fn new() -> Self {
+        SerialRx {
+            buffer: None,
+            busy: false,
+            pending: None,
+        }
+    }
+}
+
+/// Add a byte to the transmit buffer
+///
+/// A full buffer is sent first, which blocks until it has been transmitted.
+fn serial_write(
+    uarte: &uarte0::RegisterBlock,
+    tx: &mut SerialTx,
+    word: u8,
+) -> nb::Result<(), Error> {
+    if tx.len == SERIAL_TX_BUFFER_SIZE {
+        serial_flush(uarte, tx)?;
+    }
+
+    tx.buffer[tx.len] = word;
+    tx.len += 1;
+    Ok(())
+}
+
+/// Send the buffered bytes and wait until they have been transmitted
+///
+/// The bytes stay buffered if this fails.
+fn serial_flush(uarte: &uarte0::RegisterBlock, tx: &mut SerialTx) -> Result<(), Error> {
+    if tx.len > 0 {
+        write_tx_dma(uarte, &tx.buffer[..tx.len])?;
+        tx.len = 0;
+    }
+    Ok(())
+}
+
+/// Write all bytes of `buffer` after any bytes buffered by `serial::Write`,
+/// and wait until they have been transmitted
+fn serial_write_all(
+    uarte: &uarte0::RegisterBlock,
+    tx: &mut SerialTx,
+    buffer: &[u8],
+) -> Result<(), Error> {
+    serial_flush(uarte, tx)?;
+    for chunk in buffer.chunks(EASY_DMA_SIZE) {
+        write_tx(uarte, chunk)?;
+    }
+    Ok(())
+}
+
+/// Read a single byte
+///
+/// The first call starts receiving the byte and returns `WouldBlock` until it
+/// has arrived. The receiver keeps running in between, so bytes that arrive
+/// before the next call are held in the receive FIFO.
+fn serial_read(uarte: &uarte0::RegisterBlock, rx: &mut SerialRx) -> nb::Result<u8, Error> {
+    if let Some(byte) = rx.pending.take() {
+        return Ok(byte);
+    }
+
+    let buffer = rx
+        .buffer
+        .as_mut()
+        .expect("`set_serial_rx_buffer` has to be called before `serial::Read::read`");
+    if !rx.busy {
+        start_rx(uarte, &mut buffer[..])?;
+        rx.busy = true;
+    }
+    if uarte.events_endrx.read().bits() == 0 {
+        return Err(nb::Error::WouldBlock);
+    }
+
+    finalize_rx(uarte);
+    rx.busy = false;
+    take_rx_error(uarte)?;
+    Ok(buffer[0])
+}
+
+/// Run the blocking read `read` after stopping `serial::Read`
+///
+/// A byte that `serial::Read` has already received is put into the start of
+/// `rx_buffer`, and `read` only fills the rest, so the bytes stay in order.
+fn read_after_serial<F>(
+    uarte: &uarte0::RegisterBlock,
+    rx: &mut SerialRx,
+    rx_buffer: &mut [u8],
+    read: F,
+) -> Result<(), Error>
+where
+    F: FnOnce(&mut [u8]) -> Result<(), Error>,
+{
+    stop_serial_rx(uarte, rx);
+    let n = take_pending(rx, rx_buffer);
+    if n > 0 && n == rx_buffer.len() {
+        return Ok(());
+    }
+
+    read(&mut rx_buffer[n..]).map_err(|e| match e {
+        Error::Timeout(received) => Error::Timeout(n + received),
+        e => e,
+    })
+}
+
+/// `read_rx_until` after stopping `serial::Read`, see `read_after_serial`
+fn read_until_after_serial<R>(
+    uarte: &uarte0::RegisterBlock,
+    rx: &mut SerialRx,
+    rx_buffer: &mut [u8],
+    delimiter: u8,
+    rtc: &mut Rtc<R, Started>,
+    reg: RtcCompareReg,
+    idle_ticks: u32,
+) -> Result<usize, Error>
+where
+    R: rtc::Instance,
+{
+    stop_serial_rx(uarte, rx);
+    let n = take_pending(rx, rx_buffer);
+    if n > 0 && (n == rx_buffer.len() || rx_buffer[0] == delimiter) {
+        return Ok(n);
+    }
+
+    read_rx_until(uarte, &mut rx_buffer[n..], delimiter, rtc, reg, idle_ticks)
+        .map(|received| n + received)
+}
+
+/// Move a byte that `serial::Read` has already received into the start of
+/// `rx_buffer`, and return the number of bytes moved
+fn take_pending(rx: &mut SerialRx, rx_buffer: &mut [u8]) -> usize {
+    match rx_buffer.first_mut() {
+        Some(first) => match rx.pending.take() {
+            Some(byte) => {
+                *first = byte;
+                1
+            }
+            None => 0,
+        },
+        None => 0,
+    }
+}
+
+/// Stop a reception started by `serial::Read`
+///
+/// A byte that has already been received is kept, and returned by the next
+/// read.
+fn stop_serial_rx(uarte: &uarte0::RegisterBlock, rx: &mut SerialRx) {
+    if !rx.busy {
+        return;
+    }
+    rx.busy = false;
+
+    if uarte.events_endrx.read().bits() == 0 {
+        cancel_rx(uarte);
+    }
+    finalize_rx(uarte);
+
+    if uarte.rxd.amount.read().bits() != 0 {
+        rx.pending = rx.buffer.as_ref().map(|buffer| buffer[0]);
+    }
+}
+
+impl<T> serial::Write<u8> for Uarte<T>
+where
+    T: Instance,
+{
+    type Error = Error;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), Error> {
+        serial_write(&self.0, &mut self.1, word)
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Error> {
+        serial_flush(&self.0, &mut self.1).map_err(nb::Error::Other)
+    }
+}
+
+impl<T> serial::Read<u8> for Uarte<T>
+where
+    T: Instance,
+{
+    type Error = Error;
+
+    /// Read a single byte
+    ///
+    /// # Panics
+    ///
+    /// If no buffer has been set with `Uarte::set_serial_rx_buffer`.
+    fn read(&mut self) -> nb::Result<u8, Error> {
+        serial_read(&self.0, &mut self.2)
+    }
+}
+
+impl<T> blocking::serial::Write<u8> for Uarte<T>
+where
+    T: Instance,
+{
+    type Error = Error;
+
+    fn bwrite_all(&mut self, buffer: &[u8]) -> Result<(), Error> {
+        serial_write_all(&self.0, &mut self.1, buffer)
+    }
+
+    fn bflush(&mut self) -> Result<(), Error> {
+        serial_flush(&self.0, &mut self.1)
+    }
+}
+
+/// The transmitting half of a `Uarte`, created by `Uarte::split`
+///
+/// It owns the UARTE instance, which is handed back by `Uarte::join`.
+pub struct UarteTx<T> {
+    uarte: T,
+    serial: SerialTx,
+}
+
+impl<T> UarteTx<T>
+where
+    T: Instance,
+{
+    /// Write via UARTE, see `Uarte::write`
+    pub fn write(&mut self, tx_buffer: &[u8]) -> Result<(), Error> {
+        serial_flush(&self.uarte, &mut self.serial)?;
+        write_tx(&self.uarte, tx_buffer)
+    }
+
+    /// Start writing without waiting for the transmission to end, see
+    /// `Uarte::start_write`
+    pub fn start_write(mut self, tx_buffer: &'static [u8]) -> Result<TxTransfer<T>, (Error, Self)> {
+        if let Err(e) = serial_flush(&self.uarte, &mut self.serial) {
+            return Err((e, self));
+        }
+        match start_tx(&self.uarte, tx_buffer) {
+            Ok(()) => Ok(TxTransfer {
+                tx: self,
+                buffer: tx_buffer,
+            }),
+            Err(e) => Err((e, self)),
+        }
+    }
+
+    /// Enable the `ENDTX` interrupt
+    ///
+    /// `INTENSET` and `INTENCLR` only change the bits that are written, so
+    /// this doesn't affect the interrupts of the receiving half. The
+    /// interrupt also has to be unmasked in the NVIC, or the handler won't
+    /// get called.
+    pub fn enable_interrupt(&mut self) {
+        self.uarte
+            .intenset
+            .write(|w| unsafe { w.bits(UarteEvent::EndTx.mask()) });
+    }
+
+    /// Disable the `ENDTX` interrupt
+    pub fn disable_interrupt(&mut self) {
+        self.uarte
+            .intenclr
+            .write(|w| unsafe { w.bits(UarteEvent::EndTx.mask()) });
+    }
+
+    /// Obtain the state of the `ENDTX` event, and optionally clear the event
+    /// if it is set
+    pub fn get_event_triggered(&mut self, clear_on_read: bool) -> bool {
+        event_triggered(&self.uarte, UarteEvent::EndTx, clear_on_read)
+    }
+}
+
+impl<T> fmt::Write for UarteTx<T>
+where
+    T: Instance,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        write_str(&self.uarte, &mut self.serial, s)
+    }
+}
+
+impl<T> serial::Write<u8> for UarteTx<T>
+where
+    T: Instance,
+{
+    type Error = Error;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), Error> {
+        serial_write(&self.uarte, &mut self.serial, word)
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Error> {
+        serial_flush(&self.uarte, &mut self.serial).map_err(nb::Error::Other)
+    }
+}
+
+impl<T> blocking::serial::Write<u8> for UarteTx<T>
+where
+    T: Instance,
+{
+    type Error = Error;
+
+    fn bwrite_all(&mut self, buffer: &[u8]) -> Result<(), Error> {
+        serial_write_all(&self.uarte, &mut self.serial, buffer)
+    }
+
+    fn bflush(&mut self) -> Result<(), Error> {
+        serial_flush(&self.uarte, &mut self.serial)
+    }
+}
+
+/// The receiving half of a `Uarte`, created by `Uarte::split`
+pub struct UarteRx<T> {
+    serial: SerialRx,
+    _marker: PhantomData<T>,
+}
+
+impl<T> UarteRx<T>
+where
+    T: Instance,
+{
+    fn uarte(&self) -> &'static uarte0::RegisterBlock {
+        // The `UarteTx` half owns the instance, but this half only uses the
+        // receive registers and events
+        unsafe { &*T::ptr() }
+    }
+
+    /// Set the buffer used by `serial::Read`, see
+    /// `Uarte::set_serial_rx_buffer`
+    pub fn set_serial_rx_buffer(&mut self, buffer: &'static mut [u8; 1]) {
+        stop_serial_rx(self.uarte(), &mut self.serial);
+        self.serial.buffer = Some(buffer);
+    }
+
+    /// Read via UARTE, see `Uarte::read`
+    pub fn read(&mut self, rx_buffer: &mut [u8]) -> Result<(), Error> {
+        let uarte = self.uarte();
+        read_after_serial(uarte, &mut self.serial, rx_buffer, |buf| {
+            read_rx(uarte, buf)
+        })
+    }
+
+    /// Read via UARTE with a timeout, see `Uarte::read_timeout`
+    pub fn read_timeout<I>(
+        &mut self,
+        rx_buffer: &mut [u8],
+        timer: &mut Timer<I>,
+        cycles: u32,
+    ) -> Result<(), Error>
+    where
+        I: timer::Instance,
+    {
+        let uarte = self.uarte();
+        read_after_serial(uarte, &mut self.serial, rx_buffer, |buf| {
+            read_rx_timeout(uarte, buf, timer, cycles)
+        })
+    }
+
+    /// Read via UARTE with an RTC timeout, see `Uarte::read_timeout_rtc`
+    pub fn read_timeout_rtc<R>(
+        &mut self,
+        rx_buffer: &mut [u8],
+        rtc: &mut Rtc<R, Started>,
+        reg: RtcCompareReg,
+        ticks: u32,
+    ) -> Result<(), Error>
+    where
+        R: rtc::Instance,
+    {
+        let uarte = self.uarte();
+        read_after_serial(uarte, &mut self.serial, rx_buffer, |buf| {
+            read_rx_timeout_rtc(uarte, buf, rtc, reg, ticks)
+        })
+    }
+
+    /// Read via UARTE until a delimiter or idle period, see
+    /// `Uarte::read_until`
+    pub fn read_until<R>(
+        &mut self,
+        rx_buffer: &mut [u8],
+        delimiter: u8,
+        rtc: &mut Rtc<R, Started>,
+        reg: RtcCompareReg,
+        idle_ticks: u32,
+    ) -> Result<usize, Error>
+    where
+        R: rtc::Instance,
+    {
+        read_until_after_serial(
+            self.uarte(),
+            &mut self.serial,
+            rx_buffer,
+            delimiter,
+            rtc,
+            reg,
+            idle_ticks,
+        )
+    }
+
+    /// Start reading without waiting for the buffer to be filled, see
+    /// `Uarte::start_read`
+    pub fn start_read(
+        mut self,
+        rx_buffer: &'static mut [u8],
+    ) -> Result<RxTransfer<T>, (Error, Self)> {
+        stop_serial_rx(self.uarte(), &mut self.serial);
+        if self.serial.pending.is_some() {
+            return Err((Error::PendingByte, self));
+        }
+        match start_rx(self.uarte(), rx_buffer) {
+            Ok(()) => Ok(RxTransfer {
+                rx: self,
+                buffer: rx_buffer,
+            }),
+            Err(e) => Err((e, self)),
+        }
+    }
+
+    /// Enable the interrupt for an event of the receiving half
+    ///
+    /// `INTENSET` and `INTENCLR` only change the bits that are written, so
+    /// this doesn't affect the interrupt of the transmitting half. The
+    /// interrupt also has to be unmasked in the NVIC, or the handler won't
+    /// get called.
+    pub fn enable_interrupt(&mut self, evt: RxEvent) {
+        self.uarte()
+            .intenset
+            .write(|w| unsafe { w.bits(evt.event().mask()) });
+    }
+
+    /// Disable the interrupt for an event of the receiving half
+    pub fn disable_interrupt(&mut self, evt: RxEvent) {
+        self.uarte()
+            .intenclr
+            .write(|w| unsafe { w.bits(evt.event().mask()) });
+    }
+
+    /// Obtain the state of an event of the receiving half, and optionally
+    /// clear the event if it is set
+    pub fn get_event_triggered(&mut self, evt: RxEvent, clear_on_read: bool) -> bool {
+        event_triggered(self.uarte(), evt.event(), clear_on_read)
+    }
+
+    /// Return and clear the last reception error, see `Uarte::take_error`
+    pub fn take_error(&mut self) -> Result<(), Error> {
+        take_rx_error(self.uarte())
+    }
+}
+
+impl<T> serial::Read<u8> for UarteRx<T>
+where
+    T: Instance,
+{
+    type Error = Error;
+
+    /// Read a single byte
+    ///
+    /// # Panics
+    ///
+    /// If no buffer has been set with `UarteRx::set_serial_rx_buffer`.
+    fn read(&mut self) -> nb::Result<u8, Error> {
+        serial_read(self.uarte(), &mut self.serial)
+    }
+}
+
+/// A transmission of a `UarteTx` that runs in the background
+///
+/// Created by `UarteTx::start_write`. Like `Transfer`, but only holds the
+/// transmitting half.
+pub struct TxTransfer<T> {
+    tx: UarteTx<T>,
+    buffer: &'static [u8],
+}
+
+impl<T> TxTransfer<T>
+where
+    T: Instance,
+{
+    /// Has the transmission ended?
+    pub fn is_done(&self) -> bool {
+        self.tx.uarte.events_endtx.read().bits() != 0
+    }
+
+    /// Block until the transmission has ended, and return the `UarteTx` and
+    /// the buffer
+    ///
+    /// This clears the `ENDTX` event.
+    pub fn wait(self) -> (UarteTx<T>, &'static [u8]) {
+        end_tx(&self.tx.uarte);
+        (self.tx, self.buffer)
+    }
+}
+
+/// A reception of a `UarteRx` that runs in the background
+///
+/// Created by `UarteRx::start_read`. Like `Transfer`, but only holds the
+/// receiving half.
+pub struct RxTransfer<T> {
+    rx: UarteRx<T>,
+    buffer: &'static mut [u8],
+}
+
+impl<T> RxTransfer<T>
+where
+    T: Instance,
+{
+    /// Has the buffer been filled?
+    pub fn is_done(&self) -> bool {
+        self.rx.uarte().events_endrx.read().bits() != 0
+    }
+
+    /// Block until the buffer has been filled, and return the `UarteRx` and
+    /// the buffer
+    ///
+    /// This clears the `ENDRX` event. Errors during the reception can be
+    /// checked afterwards with `UarteRx::take_error`.
+    pub fn wait(self) -> (UarteRx<T>, &'static mut [u8]) {
+        let uarte = self.rx.uarte();
+        while uarte.events_endrx.read().bits() == 0 {}
+
+        finalize_rx(uarte);
+
+        (self.rx, self.buffer)
+    }
+
+    /// Stop the reception, see `Transfer::cancel`
+    pub fn cancel(self) -> (UarteRx<T>, &'static mut [u8], usize) {
+        let RxTransfer { rx, buffer } = self;
+        let received = stop_rx(rx.uarte(), buffer);
+
+        (rx, buffer, received)
+    }
+}
+
+pub struct Pins {
+    pub rxd: Pin<Input<Floating>>,
+    pub txd: Pin<Output<PushPull>>,
+    pub cts: Option<Pin<Input<Floating>>>,
+    pub rts: Option<Pin<Output<PushPull>>>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    TxBufferTooLong,
+    RxBufferTooLong,
+    Transmit,
+    Receive,
+    Timeout(usize),
+    BufferNotInRAM,
+    /// Received bytes were overwritten before they were read
+    BufferOverrun,
+    /// A byte was received while the receive FIFO was full
+    Overrun,
+    /// A byte was received with the wrong parity
+    Parity,
+    /// A byte was received without a valid stop bit
+    Framing,
+    /// RXD was held low for longer than a whole frame
+    Break,
+    /// A byte received by `serial::Read` has to be read with it before a
+    /// non-blocking reception can be started
+    PendingByte,
+}
+
+impl<T> Uarte<T>
+where
+    T: Instance,
+{
+    /// Receive continuously into `buffer`, which is used as a ring buffer
+    ///
+    /// The buffer is split into two halves, which EasyDMA fills in turn. The
+    /// `ENDRX_STARTRX` short restarts reception into the other half as soon
+    /// as one is full, so no bytes are lost between the two.
+    ///
+    /// Two timers and three PPI channels are needed to track the reception:
+    /// - `counter` counts `RXDRDY` events, i.e. the number of bytes received
+    /// - `idle` is restarted by every `RXDRDY` event and fires its `COMPARE[0]`
+    ///   event after `idle_ticks` microseconds without a byte, which captures
+    ///   the count into `CC[2]` of `counter`
+    ///
+    /// `RXDRDY` is generated before EasyDMA has written the byte to RAM, so
+    /// `RingBuffer::read` only returns the bytes of the half that is being
+    /// filled once the line has been idle.
+    ///
+    /// The PPI channels in `ppi_channels` must be below 20 and must not be used
+    /// for anything else until the ring buffer is freed.
+    ///
+    /// The buffer must have an even length, and each half must have a length
+    /// of at most 255 bytes on the nRF52832 and 65535 bytes on the nRF52840.
+    ///
+    /// This is not available on the nRF9160, which has DPPI instead of PPI.
+    #[cfg(not(feature = "9160"))]
+    pub fn into_ring_buffer<C, I>(
+        mut self,
+        buffer: &'static mut [u8],
+        counter: C,
+        idle: I,
+        idle_ticks: u32,
+        ppi: &mut PPI,
+        ppi_channels: [usize; 3],
+    ) -> RingBuffer<T, C, I>
+    where
+        C: timer::Instance,
+        I: timer::Instance,
+    {
+        stop_serial_rx(&self.0, &mut self.2);
+        assert!(!buffer.is_empty() && buffer.len() % 2 == 0);
+        assert!(buffer.len() / 2 <= EASY_DMA_SIZE);
+        let [first, second, third] = ppi_channels;
+        assert!(first < PPI_CHANNELS && second < PPI_CHANNELS && third < PPI_CHANNELS);
+        assert!(first != second && first != third && second != third);
+
+        // Count the received bytes
+        let count = counter.as_timer0();
+        count.tasks_stop.write(|w| unsafe { w.bits(1) });
+        count.mode.write(|w| unsafe { w.bits(2) }); // Low power counter
+        count.bitmode.write(|w| w.bitmode()._32bit());
+        count.tasks_start.write(|w| unsafe { w.bits(1) });
+
+        // Stop after `idle_ticks` without `RXDRDY`, which clears and starts it
+        idle.set_oneshot();
+        let timeout = idle.as_timer0();
+        timeout.tasks_stop.write(|w| unsafe { w.bits(1) });
+        timeout.cc[0].write(|w| unsafe { w.bits(idle_ticks) });
+        timeout.tasks_clear.write(|w| unsafe { w.bits(1) });
+        timeout.events_compare[0].reset();
+
+        let rxdrdy = &self.0.events_rxdrdy as *const _ as u32;
+        ppi.ch[first].eep.write(|w| unsafe { w.bits(rxdrdy) });
+        ppi.ch[first]
+            .tep
+            .write(|w| unsafe { w.bits(&count.tasks_count as *const _ as u32) });
+        ppi.fork[first]
+            .tep
+            .write(|w| unsafe { w.bits(&timeout.tasks_clear as *const _ as u32) });
+        ppi.ch[second].eep.write(|w| unsafe { w.bits(rxdrdy) });
+        ppi.ch[second]
+            .tep
+            .write(|w| unsafe { w.bits(&timeout.tasks_start as *const _ as u32) });
+        ppi.ch[third]
+            .eep
+            .write(|w| unsafe { w.bits(&timeout.events_compare[0] as *const _ as u32) });
+        ppi.ch[third]
+            .tep
+            .write(|w| unsafe { w.bits(&count.tasks_capture[2] as *const _ as u32) });
+        ppi.chenset
+            .write(|w| unsafe { w.bits(1 << first | 1 << second | 1 << third) });
+
+        let mut ring = RingBuffer {
+            uarte: self,
+            counter,
+            idle,
+            buffer,
+            ppi_channels,
+            read: 0,
+            read_index: 0,
+            ended: 0,
+            next_half: 0,
+            restarted: false,
+        };
+        ring.start();
+        ring
+    }
+}
+
+/// Continuous reception into a ring buffer
+///
+/// Created by `Uarte::into_ring_buffer`. `service` has to be called after
+/// each `RXSTARTED` event, before the half of the buffer that is being
+/// filled is full. Call it from the UARTE interrupt handler after enabling
+/// the interrupts with `enable_interrupts`, or call `read` often enough. If
+/// it is called too late, reception is restarted and the next `read`
+/// returns `Error::BufferOverrun`.
+#[cfg(not(feature = "9160"))]
+pub struct RingBuffer<T, C, I> {
+    uarte: Uarte<T>,
+    counter: C,
+    idle: I,
+    buffer: &'static mut [u8],
+    ppi_channels: [usize; 3],
+    /// Number of bytes read so far, wrapping like the counter
+    read: u32,
+    /// Position of the next byte to read in the buffer
+    read_index: usize,
+    /// Number of bytes received into halves that EasyDMA has finished
+    ended: u32,
+    /// The half of the buffer to receive into after the next `RXSTARTED`
+    next_half: usize,
+    /// Reception was restarted because `service` was called too late
+    restarted: bool,
+}
+
+#[cfg(not(feature = "9160"))]
+impl<T, C, I> RingBuffer<T, C, I>
+where
+    T: Instance,
+    C: timer::Instance,
+    I: timer::Instance,
+{
+    /// Queue the next half of the buffer after reception has been restarted
+    pub fn service(&mut self) {
+        let uarte = &self.uarte.0;
+        if uarte.events_rxstarted.read().bits() == 0 {
+            return;
+        }
+        uarte.events_rxstarted.reset();
+        uarte.events_endrx.reset();
+
+        // `RXD.PTR` is double buffered, the new value is used by the next
+        // `STARTRX`, which the short triggers when the current half is full
+        let half = self.buffer.len() / 2;
+        let next = self.buffer.as_mut_ptr() as u32 + (self.next_half * half) as u32;
+        uarte.rxd.ptr.write(|w| unsafe { w.ptr().bits(next) });
+        self.next_half ^= 1;
+        self.ended = self.ended.wrapping_add(half as u32);
+
+        // If the current half was full before its successor was queued, the
+        // short has restarted reception into it again and overwritten it.
+        // This also covers several `RXSTARTED` events that were missed.
+        let filling = self.counter.read_counter().wrapping_sub(self.ended);
+        if filling as usize >= half {
+            self.restart();
+        }
+    }
+
+    /// Number of bytes that `read` can return
+    pub fn available(&self) -> usize {
+        let pending = self.uarte.2.pending.is_some() as usize;
+        self.ready(self.counter.read_counter()) + pending
+    }
+
+    /// Read received bytes into `buf`, returning the number of bytes read
+    ///
+    /// Returns `Error::BufferOverrun` if the ring buffer has overflowed
+    /// since the last read, or while the bytes were copied into `buf`. In
+    /// that case all unread bytes are discarded, and `buf` holds no valid
+    /// data.
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        // A byte that `serial::Read` received before comes first
+        if take_pending(&mut self.uarte.2, buf) > 0 {
+            return Ok(1);
+        }
+
+        self.service();
+        if core::mem::replace(&mut self.restarted, false) {
+            return Err(Error::BufferOverrun);
+        }
+
+        let received = self.counter.read_counter();
+        let available = received.wrapping_sub(self.read) as usize;
+        if available > self.buffer.len() {
+            return Err(self.overrun(received));
+        }
+
+        // Second fence to prevent optimizations creating issues with the
+        // EasyDMA-modified buffer
+        compiler_fence(SeqCst);
+
+        let len = self.ready(received).min(buf.len());
+        let base = self.buffer.as_ptr();
+        for byte in &mut buf[..len] {
+            // EasyDMA writes to the other parts of the buffer concurrently,
+            // so don't create a reference to it
+            *byte = unsafe { core::ptr::read_volatile(base.add(self.read_index)) };
+            self.read_index = (self.read_index + 1) % self.buffer.len();
+        }
+
+        // EasyDMA could have overwritten the first bytes while they were
+        // copied, if more bytes than fit into the buffer arrived since then
+        let received = self.counter.read_counter();
+        if received.wrapping_sub(self.read) as usize > self.buffer.len() {
+            self.read_index = (self.read_index + self.buffer.len() - len) % self.buffer.len();
+            return Err(self.overrun(received));
+        }
+        self.read = self.read.wrapping_add(len as u32);
+
+        Ok(len)
+    }
+
+    /// Number of unread bytes that are known to be in RAM
+    ///
+    /// `received` is the current value of the counter. These are the bytes of
+    /// the halves that EasyDMA has finished, and the bytes that had been
+    /// received when the line went idle, which the idle timer captured.
+    fn ready(&self, received: u32) -> usize {
+        let available = received.wrapping_sub(self.read);
+        let idle = self.counter.as_timer0().cc[2].read().bits();
+
+        // A count from before the last read wraps around to a large number
+        [self.ended, idle]
+            .iter()
+            .map(|count| count.wrapping_sub(self.read))
+            .filter(|&ready| ready <= available)
+            .max()
+            .unwrap_or(0) as usize
+    }
+
+    /// Discard all unread bytes after an overrun
+    ///
+    /// `received` is the current value of the counter.
+    fn overrun(&mut self, received: u32) -> Error {
+        let skipped = received.wrapping_sub(self.read) as usize % self.buffer.len();
+        self.read = received;
+        self.read_index = (self.read_index + skipped) % self.buffer.len();
+        Error::BufferOverrun
+    }
+
+    /// Start reception into the first half and queue the second one
+    fn start(&mut self) {
+        let count = self.counter.as_timer0();
+        count.tasks_clear.write(|w| unsafe { w.bits(1) });
+        count.cc[2].write(|w| unsafe { w.bits(0) });
+        self.read = 0;
+        self.read_index = 0;
+        self.ended = 0;
+        self.next_half = 0;
+
+        let uarte = &self.uarte.0;
+        let half = self.buffer.len() / 2;
+        let start = self.buffer.as_mut_ptr() as u32;
+
+        // Conservative compiler fence to prevent optimizations that do not
+        // take in to account actions by DMA. The fence has been placed here,
+        // before any DMA action has started
+        compiler_fence(SeqCst);
+
+        uarte.events_rxstarted.reset();
+        uarte.events_endrx.reset();
+        uarte.rxd.ptr.write(|w| unsafe { w.ptr().bits(start) });
+        uarte
+            .rxd
+            .maxcnt
+            .write(|w| unsafe { w.maxcnt().bits(half as _) });
+        uarte.shorts.modify(|_, w| w.endrx_startrx().enabled());
+        uarte.tasks_startrx.write(|w| unsafe { w.bits(1) });
+
+        while uarte.events_rxstarted.read().bits() == 0 {}
+        uarte.events_rxstarted.reset();
+        uarte
+            .rxd
+            .ptr
+            .write(|w| unsafe { w.ptr().bits(start + half as u32) });
+    }
+
+    /// Stop reception, discarding the received bytes
+    fn stop(&mut self) {
+        let uarte = &self.uarte.0;
+        uarte.shorts.modify(|_, w| w.endrx_startrx().disabled());
+        uarte.tasks_stoprx.write(|w| unsafe { w.bits(1) });
+        while uarte.events_rxto.read().bits() == 0 {}
+        uarte.events_rxto.reset();
+
+        // Empty the RX FIFO, so that the bytes counted before a restart
+        // don't end up in the buffer after it. `ENDRX` is generated before
+        // `RXTO`, and again by `FLUSHRX`.
+        uarte.events_endrx.reset();
+        uarte.tasks_flushrx.write(|w| unsafe { w.bits(1) });
+        while uarte.events_endrx.read().bits() == 0 {}
+        uarte.events_endrx.reset();
+        uarte.events_rxstarted.reset();
+
+        // Conservative compiler fence to prevent optimizations that do not
+        // take in to account actions by DMA. The fence has been placed here,
+        // after all possible DMA actions have completed
+        compiler_fence(SeqCst);
+    }
+
+    /// Restart reception after `service` was called too late
+    fn restart(&mut self) {
+        self.stop();
+        self.start();
+        self.restarted = true;
+    }
+
+    /// Has the line been idle for `idle_ticks` since the last byte, and
+    /// optionally clear the condition if it is set
+    ///
+    /// This can be used to deliver frames that are shorter than the buffer.
+    pub fn is_idle(&mut self, clear_on_read: bool) -> bool {
+        let idle = self.idle.timer_running();
+        if !idle && clear_on_read {
+            self.idle.timer_reset_event();
+        }
+        !idle
+    }
+
+    /// Enable the `RXSTARTED` interrupt of the UARTE and the `COMPARE[0]`
+    /// interrupt of the idle timer
+    ///
+    /// Both interrupts also have to be unmasked in the NVIC, or the handlers
+    /// won't get called.
+    pub fn enable_interrupts(&mut self) {
+        self.uarte
+            .0
+            .intenset
+            .write(|w| unsafe { w.bits(RXSTARTED_MASK) });
+        self.idle.enable_interrupt();
+    }
+
+    /// Disable the interrupts enabled by `enable_interrupts`
+    pub fn disable_interrupts(&mut self) {
+        self.uarte
+            .0
+            .intenclr
+            .write(|w| unsafe { w.bits(RXSTARTED_MASK) });
+        self.idle.disable_interrupt();
+    }
+
+    /// Stop reception and release the `Uarte`, buffer and timers
+    pub fn free(mut self, ppi: &mut PPI) -> (Uarte<T>, &'static mut [u8], C, I) {
+        self.stop();
+
+        let [first, second, third] = self.ppi_channels;
+        ppi.chenclr
+            .write(|w| unsafe { w.bits(1 << first | 1 << second | 1 << third) });
+        self.counter
+            .as_timer0()
+            .tasks_stop
+            .write(|w| unsafe { w.bits(1) });
+        self.idle.timer_cancel();
+
+        (self.uarte, self.buffer, self.counter, self.idle)
+    }
+}
+
+/// Number of configurable PPI channels
+#[cfg(not(feature = "9160"))]
+const PPI_CHANNELS: usize = 20;
+
+/// Bit of the `RXSTARTED` event in `INTEN`
+#[cfg(not(feature = "9160"))]
+const RXSTARTED_MASK: u32 = 1 << 19;
+
+/// Events of the UARTE peripheral
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UarteEvent {
+    /// The receive buffer has been filled
+    EndRx,
+    /// The transmit buffer has been sent
+    EndTx,
+    /// An error occurred during reception
+    Error,
+}
+
+/// Events of the receiving half of a split `Uarte`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RxEvent {
+    /// The receive buffer has been filled
+    EndRx,
+    /// An error occurred during reception
+    Error,
+}
+
+impl RxEvent {
+    fn event(self) -> UarteEvent {
+        match self {
+            RxEvent::EndRx => UarteEvent::EndRx,
+            RxEvent::Error => UarteEvent::Error,
+        }
+    }
+}
+
+impl UarteEvent {
+    /// Bit of the event in `INTEN`
+    fn mask(self) -> u32 {
+        match self {
+            UarteEvent::EndRx => 1 << 4,
+            UarteEvent::EndTx => 1 << 8,
+            UarteEvent::Error => 1 << 9,
+        }
+    }
+}
+
+pub trait Instance: Deref<Target = uarte0::RegisterBlock> {
+    /// Pointer to the register block of the instance
+    fn ptr() -> *const uarte0::RegisterBlock;
+}
+
+macro_rules! impl_instance {
+    ($($name:ident,)*) => {
+        $(
+            impl Instance for $name {
+                fn ptr() -> *const uarte0::RegisterBlock {
+                    $name::ptr()
+                }
+            }
+        )*
+    }
+}
+
+impl_instance!(UARTE0,);
+
+#[cfg(any(feature = "52840", feature = "9160"))]
+impl_instance!(UARTE1,);
+
+// The secure aliases of the nRF9160 share the register layout of the
+// non-secure ones. Which of them is accessible depends on the SPU setup.
+#[cfg(feature = "9160")]
+impl_instance!(UARTE0_S, UARTE1_S, UARTE2_NS, UARTE2_S, UARTE3_NS, UARTE3_S,);
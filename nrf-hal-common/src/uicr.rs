@@ -7,10 +7,14 @@
 //! - nrf52840: Section 4.5
 use crate::target::{NVMC, UICR};
 
+#[cfg(not(feature = "51"))]
+use core::ptr;
+
 /// Interface to a UICR instance
 ///
 /// This is a very basic interface that comes with the following limitations:
-/// - Only `customer` registers are usable for storing and loading of data
+/// - Only `customer` registers and the typed configuration registers (see
+///   `ConfigRegister`) are usable
 /// - Erase must be performed in order to write bits with value `1` over `0`
 pub struct Uicr(UICR);
 
@@ -76,4 +80,283 @@ impl Uicr {
 
         values
     }
+
+    /// Read a typed configuration register
+    #[cfg(not(feature = "51"))]
+    pub fn read<R: ConfigRegister>(&self) -> R {
+        R::from_bits(self.read_word(R::OFFSETS[0]))
+    }
+
+    /// Write a typed configuration register
+    ///
+    /// UICR bits can only be programmed from `1` to `0`. If the new value
+    /// requires any bit to go from `0` to `1`, nothing is written and
+    /// `Error::EraseRequired` is returned. Use `erase_and_write` in that case.
+    #[cfg(not(feature = "51"))]
+    pub fn write<R: ConfigRegister>(&mut self, nvmc: &mut NVMC, value: R) -> Result<Change, Error> {
+        let bits = value.to_bits();
+
+        let mut unchanged = true;
+        for &offset in R::OFFSETS {
+            let current = self.read_word(offset);
+            if current & bits != bits {
+                return Err(Error::EraseRequired);
+            }
+            unchanged &= current == bits;
+        }
+
+        if unchanged {
+            return Ok(Change::None);
+        }
+
+        for &offset in R::OFFSETS {
+            self.write_word(nvmc, offset, bits);
+        }
+
+        Ok(Change::ResetRequired)
+    }
+
+    /// Write a typed configuration register, erasing the UICR if required
+    ///
+    /// All other UICR contents, including the `customer` registers, are read
+    /// before the erase and written back afterwards. A reset or power loss
+    /// before this function returns leaves the UICR partially erased.
+    #[cfg(not(feature = "51"))]
+    pub fn erase_and_write<R: ConfigRegister>(&mut self, nvmc: &mut NVMC, value: R) -> Change {
+        if let Ok(change) = self.write(nvmc, value.clone()) {
+            return change;
+        }
+
+        let mut image = [0u32; UICR_WORDS];
+        for (i, word) in image.iter_mut().enumerate() {
+            *word = self.read_word(i * 4);
+        }
+        for &offset in R::OFFSETS {
+            image[offset / 4] = value.to_bits();
+        }
+
+        self.erase(nvmc);
+
+        for (i, &word) in image.iter().enumerate() {
+            // Erased words read as all ones, no need to write them
+            if word != 0xFFFF_FFFF {
+                self.write_word(nvmc, i * 4, word);
+            }
+        }
+
+        Change::ResetRequired
+    }
+
+    #[cfg(not(feature = "51"))]
+    fn read_word(&self, offset: usize) -> u32 {
+        let base = &*self.0 as *const _ as *const u32;
+        unsafe { ptr::read_volatile(base.add(offset / 4)) }
+    }
+
+    #[cfg(not(feature = "51"))]
+    fn write_word(&mut self, nvmc: &mut NVMC, offset: usize, value: u32) {
+        assert!(nvmc.config.read().wen().is_een() == false); // write + erase is forbidden!
+
+        let base = &*self.0 as *const _ as *mut u32;
+        nvmc.config.write(|w| w.wen().wen());
+        unsafe { ptr::write_volatile(base.add(offset / 4), value) };
+        while nvmc.ready.read().ready().is_busy() {}
+        nvmc.config.reset()
+    }
+}
+
+/// Number of UICR words that are preserved by `Uicr::erase_and_write`
+#[cfg(any(feature = "52810", feature = "52832"))]
+const UICR_WORDS: usize = 0x214 / 4;
+
+/// Number of UICR words that are preserved by `Uicr::erase_and_write`
+#[cfg(feature = "52840")]
+const UICR_WORDS: usize = 0x308 / 4;
+
+/// Outcome of writing a UICR configuration register
+#[cfg(not(feature = "51"))]
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// The register already held the requested value
+    None,
+    /// The new value takes effect after the next reset
+    ResetRequired,
+}
+
+/// Error returned by `Uicr::write`
+#[cfg(not(feature = "51"))]
+#[derive(Debug)]
+pub enum Error {
+    /// The new value needs bits to change from `0` to `1`
+    EraseRequired,
+}
+
+/// A typed UICR configuration register
+#[cfg(not(feature = "51"))]
+pub trait ConfigRegister: Clone {
+    /// Byte offsets of the register(s) from the start of the UICR. All of them
+    /// are written with the same value.
+    const OFFSETS: &'static [usize];
+
+    /// Decode the raw word read from the first of the `OFFSETS`
+    ///
+    /// Every word has to map to some value, including the erased state
+    /// `0xFFFF_FFFF` and values written by other firmware.
+    fn from_bits(bits: u32) -> Self;
+
+    /// Encode the value as the raw word that is written to all `OFFSETS`
+    ///
+    /// `from_bits` has to decode the result to the same value. `Uicr::write`
+    /// can only program the word without an erase if it just clears bits of
+    /// the current one.
+    fn to_bits(&self) -> u32;
+}
+
+/// Dedicated pin reset, configured through `PSELRESET[0]` and `PSELRESET[1]`
+#[cfg(not(feature = "51"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinReset {
+    /// The reset pin is connected
+    Enabled,
+    /// The reset pin can be used as GPIO
+    Disabled,
+}
+
+/// The pin that can be configured as reset pin
+#[cfg(any(feature = "52810", feature = "52832"))]
+pub const RESET_PIN: u32 = 21;
+
+/// The pin that can be configured as reset pin
+#[cfg(feature = "52840")]
+pub const RESET_PIN: u32 = 18;
+
+#[cfg(not(feature = "51"))]
+impl ConfigRegister for PinReset {
+    const OFFSETS: &'static [usize] = &[0x200, 0x204];
+
+    fn from_bits(bits: u32) -> Self {
+        // CONNECT is bit 31, `0` means connected
+        if bits & (1 << 31) == 0 {
+            PinReset::Enabled
+        } else {
+            PinReset::Disabled
+        }
+    }
+
+    fn to_bits(&self) -> u32 {
+        match self {
+            PinReset::Enabled => RESET_PIN,
+            PinReset::Disabled => 0xFFFF_FFFF,
+        }
+    }
+}
+
+/// Function of the NFC antenna pins (P0.09 and P0.10), configured through
+/// `NFCPINS`
+#[cfg(any(feature = "52832", feature = "52840"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NfcPins {
+    /// The pins are used by the NFCT peripheral
+    Nfc,
+    /// The pins can be used as GPIO
+    Gpio,
+}
+
+#[cfg(any(feature = "52832", feature = "52840"))]
+impl ConfigRegister for NfcPins {
+    const OFFSETS: &'static [usize] = &[0x20C];
+
+    fn from_bits(bits: u32) -> Self {
+        if bits & 1 == 1 {
+            NfcPins::Nfc
+        } else {
+            NfcPins::Gpio
+        }
+    }
+
+    fn to_bits(&self) -> u32 {
+        match self {
+            NfcPins::Nfc => 0xFFFF_FFFF,
+            NfcPins::Gpio => 0xFFFF_FFFE,
+        }
+    }
+}
+
+/// Access port protection, configured through `APPROTECT`
+///
+/// Once enabled, it can only be disabled again by an `ERASEALL` through the
+/// CTRL-AP, which erases the UICR as well as the whole flash.
+#[cfg(not(feature = "51"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApProtect {
+    /// The debugger has full access
+    Disabled,
+    /// The debugger can't access the CPU or memory
+    Enabled,
+}
+
+#[cfg(not(feature = "51"))]
+impl ConfigRegister for ApProtect {
+    const OFFSETS: &'static [usize] = &[0x208];
+
+    fn from_bits(bits: u32) -> Self {
+        if bits & 0xFF == 0xFF {
+            ApProtect::Disabled
+        } else {
+            ApProtect::Enabled
+        }
+    }
+
+    fn to_bits(&self) -> u32 {
+        match self {
+            ApProtect::Disabled => 0xFFFF_FFFF,
+            ApProtect::Enabled => 0xFFFF_FF00,
+        }
+    }
+}
+
+/// Output voltage of the REG0 regulator stage in high voltage mode,
+/// configured through `REGOUT0`
+#[cfg(feature = "52840")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegOut0 {
+    V1_8,
+    V2_1,
+    V2_4,
+    V2_7,
+    V3_0,
+    V3_3,
+    /// The erased value, which results in 1.8 V
+    Default,
+}
+
+#[cfg(feature = "52840")]
+impl ConfigRegister for RegOut0 {
+    const OFFSETS: &'static [usize] = &[0x304];
+
+    fn from_bits(bits: u32) -> Self {
+        match bits & 0b111 {
+            0 => RegOut0::V1_8,
+            1 => RegOut0::V2_1,
+            2 => RegOut0::V2_4,
+            3 => RegOut0::V2_7,
+            4 => RegOut0::V3_0,
+            5 => RegOut0::V3_3,
+            _ => RegOut0::Default,
+        }
+    }
+
+    fn to_bits(&self) -> u32 {
+        let vout = match self {
+            RegOut0::V1_8 => 0,
+            RegOut0::V2_1 => 1,
+            RegOut0::V2_4 => 2,
+            RegOut0::V2_7 => 3,
+            RegOut0::V3_0 => 4,
+            RegOut0::V3_3 => 5,
+            RegOut0::Default => 7,
+        };
+        0xFFFF_FFF8 | vout
+    }
 }